@@ -5,7 +5,7 @@ use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub(crate) struct Action {
-    cmd: String,
+    cmd: Vec<String>,
     subcmd: Vec<String>,
     options: Vec<Vec<String>>,
     args: Vec<String>,
@@ -26,9 +26,9 @@ impl FromStr for Action {
             return Err(UptError::InvalidAction(s.to_string()));
         }
         let (cmd, subcmd, reminder) = if words[1].starts_with('-') || words[1] == "$" {
-            (words[0].to_string(), vec![], &words[1..])
+            (split(words[0]), vec![], &words[1..])
         } else {
-            (words[0].to_string(), split(words[1]), &words[2..])
+            (split(words[0]), split(words[1]), &words[2..])
         };
         for elem in reminder {
             if elem == &"$" {
@@ -73,11 +73,11 @@ impl Action {
         Some((pkg, confirm))
     }
 
-    pub fn to_cmd(&self, pkg: &str, confirm: &str) -> Option<Vec<String>> {
+    pub fn to_cmd(&self, pkgs: &[String], confirm: &str) -> Option<Vec<String>> {
         if self.invalid() {
             return None;
         }
-        let mut segs = vec![self.cmd.to_string()];
+        let mut segs = vec![self.cmd[0].clone()];
         if let Some(action) = self.subcmd.first() {
             segs.push(action.clone());
         }
@@ -87,9 +87,7 @@ impl Action {
         if !self.args.is_empty() {
             segs.extend(self.args.iter().cloned());
         }
-        if !pkg.is_empty() {
-            segs.push(pkg.to_string());
-        }
+        segs.extend(pkgs.iter().cloned());
         if !confirm.is_empty() {
             segs.push(confirm.to_string());
         }
@@ -100,7 +98,7 @@ impl Action {
         if self.invalid() {
             return None;
         }
-        let mut segs: Vec<String> = vec![self.cmd.clone()];
+        let mut segs: Vec<String> = vec![join(&self.cmd)];
 
         if !self.subcmd.is_empty() {
             segs.push(join(&self.subcmd));
@@ -113,12 +111,33 @@ impl Action {
                 segs.push(item[0].clone());
             }
         }
+        segs.extend(self.args.iter().cloned());
         if self.has_pkg {
             segs.push(String::from("<pkg>"));
         }
         Some(segs.join(" "))
     }
 
+    /// Command names the action accepts, the first one is used to perform it
+    pub fn cmds(&self) -> &[String] {
+        &self.cmd
+    }
+
+    /// Words that select the action, the subcommands or else the alternatives of the first option
+    pub fn heads(&self) -> &[String] {
+        match (self.subcmd.is_empty(), self.options.first()) {
+            (false, _) => &self.subcmd,
+            (true, Some(option)) => option,
+            (true, None) => &[],
+        }
+    }
+
+    /// Alternatives of the options following the heads
+    pub fn flags(&self) -> Vec<String> {
+        let skip = if self.subcmd.is_empty() { 1 } else { 0 };
+        self.options.iter().skip(skip).flatten().cloned().collect()
+    }
+
     fn invalid(&self) -> bool {
         self.cmd.is_empty()
     }
@@ -127,7 +146,7 @@ impl Action {
         if args.len() < 2 {
             return None;
         }
-        if self.cmd != args[0] {
+        if !self.cmd.contains(&args[0]) {
             return None;
         }
         let reminder = if self.subcmd.is_empty() {
@@ -205,7 +224,7 @@ mod tests {
         assert_eq!(
             Action::from_str("upt install $").unwrap(),
             Action {
-                cmd: "upt".to_string(),
+                cmd: vec!["upt".to_string()],
                 subcmd: vec!["install".to_string()],
                 options: vec![],
                 args: vec![],
@@ -215,7 +234,7 @@ mod tests {
         assert_eq!(
             Action::from_str("upt search $").unwrap(),
             Action {
-                cmd: "upt".to_string(),
+                cmd: vec!["upt".to_string()],
                 subcmd: vec!["search".to_string()],
                 options: vec![],
                 args: vec![],
@@ -225,7 +244,7 @@ mod tests {
         assert_eq!(
             Action::from_str("upt remove/uninstall $").unwrap(),
             Action {
-                cmd: "upt".to_string(),
+                cmd: vec!["upt".to_string()],
                 subcmd: vec!["remove".to_string(), "uninstall".to_string()],
                 options: vec![],
                 args: vec![],
@@ -235,7 +254,7 @@ mod tests {
         assert_eq!(
             Action::from_str("apt list --installed").unwrap(),
             Action {
-                cmd: "apt".to_string(),
+                cmd: vec!["apt".to_string()],
                 subcmd: vec!["list".to_string()],
                 options: vec![vec!["--installed".to_string()]],
                 args: vec![],
@@ -245,7 +264,7 @@ mod tests {
         assert_eq!(
             Action::from_str("pacman -R -s $").unwrap(),
             Action {
-                cmd: "pacman".to_string(),
+                cmd: vec!["pacman".to_string()],
                 subcmd: vec![],
                 options: vec![vec!["-R".to_string()], vec!["-s".to_string()]],
                 args: vec![],
@@ -255,7 +274,7 @@ mod tests {
         assert_eq!(
             Action::from_str("pacman -S -y -y").unwrap(),
             Action {
-                cmd: "pacman".to_string(),
+                cmd: vec!["pacman".to_string()],
                 subcmd: vec![],
                 options: vec![
                     vec!["-S".to_string()],
@@ -269,7 +288,7 @@ mod tests {
         assert_eq!(
             Action::from_str("pacman -S $").unwrap(),
             Action {
-                cmd: "pacman".to_string(),
+                cmd: vec!["pacman".to_string()],
                 subcmd: vec![],
                 options: vec![vec!["-S".to_string()]],
                 args: vec![],
@@ -279,7 +298,7 @@ mod tests {
         assert_eq!(
             Action::from_str("scoop update *").unwrap(),
             Action {
-                cmd: "scoop".to_string(),
+                cmd: vec!["scoop".to_string()],
                 subcmd: vec!["update".to_string()],
                 options: vec![],
                 args: vec!["*".to_string()],
@@ -289,7 +308,7 @@ mod tests {
         assert_eq!(
             Action::from_str("choco upgrade all").unwrap(),
             Action {
-                cmd: "choco".to_string(),
+                cmd: vec!["choco".to_string()],
                 subcmd: vec!["upgrade".to_string()],
                 options: vec![],
                 args: vec!["all".to_string()],
@@ -347,6 +366,13 @@ mod tests {
             ("vim jq", false)
         );
         check_action_parse!("apt install $", "-y/--confirm", ["upt", "install", "vim"]);
+        check_action_parse!(
+            "apt/apt-get install $",
+            "-y/--confirm",
+            ["apt-get", "install", "-y", "vim"],
+            ("vim", true)
+        );
+        check_action_parse!("apt/apt-get install $", "", ["apt-cache", "install", "vim"]);
         check_action_parse!("apt search $", "", ["apt", "search", "vim"], ("vim", false));
         check_action_parse!(
             "apt list --installed",
@@ -381,17 +407,21 @@ mod tests {
         check_action_parse!("pacman -Q -i", "", ["pacman", "-Qiy"]);
     }
 
+    fn words(v: &str) -> Vec<String> {
+        v.split_whitespace().map(|v| v.to_string()).collect()
+    }
+
     macro_rules! check_action_to_cmd {
         ($input:expr, ($pkg:expr, $confirm:expr), $cmd:expr) => {{
             let action = Action::from_str($input).unwrap();
             assert_eq!(
-                action.to_cmd($pkg, $confirm).map(|v| v.join(" ")),
+                action.to_cmd(&words($pkg), $confirm).map(|v| v.join(" ")),
                 Some($cmd.to_string())
             );
         }};
         ($input:expr, ($pkg:expr, $confirm:expr)) => {{
             let action = Action::from_str($input).unwrap();
-            assert!(action.to_cmd($pkg, $confirm).is_none());
+            assert!(action.to_cmd(&words($pkg), $confirm).is_none());
         }};
     }
 
@@ -400,6 +430,19 @@ mod tests {
         check_action_to_cmd!("apt install $", ("vim", ""), "apt install vim");
         check_action_to_cmd!("apt install $", ("vim", "-y"), "apt install vim -y");
         check_action_to_cmd!("apt install $", ("vim jq", ""), "apt install vim jq");
+        check_action_to_cmd!("apt/apt-get install $", ("vim", ""), "apt install vim");
+        assert_eq!(
+            Action::from_str("apt install $")
+                .unwrap()
+                .to_cmd(&words("vim jq"), "-y"),
+            Some(vec![
+                "apt".to_string(),
+                "install".to_string(),
+                "vim".to_string(),
+                "jq".to_string(),
+                "-y".to_string()
+            ])
+        );
         check_action_to_cmd!("apt search $", ("vim", ""), "apt search vim");
         check_action_to_cmd!("apt list --installed", ("", ""), "apt list --installed");
         check_action_to_cmd!(
@@ -437,5 +480,7 @@ mod tests {
         check_action_help!("upt list -i/--installed", "upt list -i/--installed");
         check_action_help!("pacman -S -y -y", "pacman -S -y -y");
         check_action_help!("pacman -S $", "pacman -S <pkg>");
+        check_action_help!("choco upgrade all", "choco upgrade all");
+        check_action_help!("apt/apt-cache search $", "apt/apt-cache search <pkg>");
     }
 }
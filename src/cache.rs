@@ -0,0 +1,170 @@
+use crate::error::UptError;
+use crate::runner::CommandRunner;
+use crate::utils::detect_os;
+use crate::vendor::{detect_tool, detect_tool_with};
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::UNIX_EPOCH;
+
+/// The os and the package management tool found on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// Id of the os, e.g. `debian`
+    pub os: String,
+    /// Name of the tool, e.g. `apt`
+    pub tool: String,
+    /// Resolved path of the tool's binary
+    pub bin: PathBuf,
+}
+
+impl Detection {
+    /// Detect the os and its tool, reusing the result of a previous run when it is still valid.
+    ///
+    /// The result is cached in `$XDG_CACHE_HOME/upt`, keyed on the mtime of /etc/os-release and `$PATH`.
+    pub fn detect() -> Result<Self, UptError> {
+        let key = cache_key();
+        if let Some(detection) = cache_path().and_then(|path| load(&path, &key)) {
+            return Ok(detection);
+        }
+        let os = detect_os().unwrap_or_default();
+        let (tool, bin) = detect_tool(&os)?;
+        let detection = Detection { os, tool, bin };
+        detection.save(&key);
+        Ok(detection)
+    }
+
+    /// Detect the os and its tool where the runner runs commands, the result is not cached
+    pub fn detect_with(runner: &dyn CommandRunner) -> Result<Self, UptError> {
+        let os = runner.detect_os().unwrap_or_default();
+        let (tool, bin) = detect_tool_with(runner, &os)?;
+        Ok(Detection { os, tool, bin })
+    }
+
+    /// The cached detection if it is still valid
+    pub(crate) fn cached() -> Option<Self> {
+        load(&cache_path()?, &cache_key())
+    }
+
+    fn save(&self, key: &str) {
+        if let Some(path) = cache_path() {
+            let _ = self.save_to(&path, key);
+        }
+    }
+
+    /// Write a temporary file renamed into place, so concurrent runs never read a partial cache
+    fn save_to(&self, path: &Path, key: &str) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension(format!("{}.tmp", process::id()));
+        fs::write(&tmp, self.serialize(key))?;
+        fs::rename(&tmp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+
+    fn serialize(&self, key: &str) -> String {
+        format!(
+            "key={}\nos={}\ntool={}\nbin={}\n",
+            key,
+            self.os,
+            self.tool,
+            self.bin.display()
+        )
+    }
+
+    fn deserialize(data: &str, key: &str) -> Option<Self> {
+        let get = |name: &str| {
+            data.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+        };
+        if get("key")? != key {
+            return None;
+        }
+        Some(Detection {
+            os: get("os")?.to_string(),
+            tool: get("tool")?.to_string(),
+            bin: PathBuf::from(get("bin")?),
+        })
+    }
+}
+
+/// The detection cached in the file, None if the key changed or the binary is gone
+fn load(path: &Path, key: &str) -> Option<Detection> {
+    let data = fs::read_to_string(path).ok()?;
+    let detection = Detection::deserialize(&data, key)?;
+    if !detection.bin.is_file() {
+        return None;
+    }
+    Some(detection)
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|v| PathBuf::from(v).join(".cache")))
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(dir.join("upt").join("detect"))
+}
+
+fn cache_key() -> String {
+    let mtime = fs::metadata("/etc/os-release")
+        .and_then(|v| v.modified())
+        .ok()
+        .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+        .map(|v| format!("{}.{}", v.as_secs(), v.subsec_nanos()))
+        .unwrap_or_else(|| "-".to_string());
+    let path = env::var("PATH").unwrap_or_default();
+    format!("{mtime}:{path}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let detection = Detection {
+            os: "ubuntu".to_string(),
+            tool: "apt".to_string(),
+            bin: PathBuf::from("/usr/bin/apt"),
+        };
+        let data = detection.serialize("1.0:/usr/bin");
+        assert_eq!(
+            Detection::deserialize(&data, "1.0:/usr/bin"),
+            Some(detection)
+        );
+        assert_eq!(Detection::deserialize(&data, "2.0:/usr/bin"), None);
+        assert_eq!(
+            Detection::deserialize("key=1.0:/usr/bin\n", "1.0:/usr/bin"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = env::temp_dir().join(format!("upt-cache-{}", process::id()));
+        let path = dir.join("upt").join("detect");
+        let bin = dir.join("apt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&bin, "").unwrap();
+        let detection = Detection {
+            os: "ubuntu".to_string(),
+            tool: "apt".to_string(),
+            bin: bin.clone(),
+        };
+        detection.save_to(&path, "1.0:/usr/bin").unwrap();
+        assert_eq!(load(&path, "1.0:/usr/bin"), Some(detection));
+        // Only the cache is left in the directory
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+        // The tool was uninstalled, it is detected again
+        fs::remove_file(&bin).unwrap();
+        assert_eq!(load(&path, "1.0:/usr/bin"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+use crate::error::UptError;
+use crate::vendor::{Vendor, ACTIONS};
+
+use std::str::FromStr;
+
+/// Shells that completion scripts can be generated for
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Shell {
+    /// Bash, sourced from bash-completion
+    Bash,
+    /// Zsh, an fpath function
+    Zsh,
+    /// Fish
+    Fish,
+    /// PowerShell, a native argument completer
+    PowerShell,
+    /// Elvish
+    Elvish,
+}
+
+impl FromStr for Shell {
+    type Err = UptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" => Ok(Shell::PowerShell),
+            "elvish" => Ok(Shell::Elvish),
+            _ => Err(UptError::InvalidArgs(format!("Unknown shell '{}'.", s))),
+        }
+    }
+}
+
+/// Completion grammar of a tool, derived from the actions of its vendor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    commands: Vec<Command>,
+}
+
+/// Words completed for a command name, e.g. apt-get
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    bin: String,
+    /// Words allowed as the first argument, paired with the words allowed after them
+    entries: Vec<(String, Vec<String>)>,
+}
+
+impl Completion {
+    /// The grammar of the tool's commands
+    pub fn new(vendor: &Vendor) -> Self {
+        let main_cmd = vendor.install.cmds().first().cloned().unwrap_or_default();
+        let mut commands: Vec<Command> = vec![];
+        let confirm: Vec<String> = vendor
+            .confirm
+            .split('/')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+        for (name, action) in ACTIONS.iter().zip(vendor.actions()) {
+            // The tool's command and the aliases it answers to, but not other tools it borrows
+            let bins = action
+                .cmds()
+                .iter()
+                .enumerate()
+                .filter(|(j, bin)| *j > 0 || **bin == main_cmd);
+            for (_, bin) in bins {
+                if !commands.iter().any(|v| &v.bin == bin) {
+                    commands.push(Command {
+                        bin: bin.clone(),
+                        entries: vec![],
+                    })
+                }
+            }
+            let mut flags = action.flags();
+            // install, remove, upgrade and upgrade_all accept the confirm options
+            if ["install", "remove", "upgrade", "upgrade_all"].contains(name) {
+                flags.extend(confirm.iter().cloned());
+            }
+            for command in commands.iter_mut() {
+                if action.cmds().contains(&command.bin) {
+                    for head in action.heads() {
+                        command.add(head, &flags);
+                    }
+                }
+            }
+        }
+        Completion { commands }
+    }
+
+    /// Add a first argument and the words allowed after it to the command `bin`
+    pub fn add(&mut self, bin: &str, head: &str, words: &[&str]) {
+        let words: Vec<String> = words.iter().map(|v| v.to_string()).collect();
+        if let Some(command) = self.commands.iter_mut().find(|v| v.bin == bin) {
+            command.add(head, &words);
+        }
+    }
+
+    /// Render the completion script for the shell
+    pub fn generate(&self, shell: Shell) -> String {
+        let scripts: Vec<String> = self
+            .commands
+            .iter()
+            .map(|command| match shell {
+                Shell::Bash => command.bash(),
+                Shell::Zsh => command.zsh(),
+                Shell::Fish => command.fish(),
+                Shell::PowerShell => command.powershell(),
+                Shell::Elvish => command.elvish(),
+            })
+            .collect();
+        scripts.join("\n")
+    }
+}
+
+impl Command {
+    fn add(&mut self, head: &str, words: &[String]) {
+        let i = match self.entries.iter().position(|(v, _)| v == head) {
+            Some(i) => i,
+            None => {
+                self.entries.push((head.to_string(), vec![]));
+                self.entries.len() - 1
+            }
+        };
+        let known = &mut self.entries[i].1;
+        for word in words {
+            if !known.contains(word) {
+                known.push(word.clone());
+            }
+        }
+    }
+
+    fn heads(&self) -> Vec<&str> {
+        self.entries.iter().map(|(v, _)| v.as_str()).collect()
+    }
+
+    /// Entries that have words to complete after the head
+    fn followed(&self) -> impl Iterator<Item = &(String, Vec<String>)> {
+        self.entries.iter().filter(|(_, words)| !words.is_empty())
+    }
+
+    fn func_name(&self) -> String {
+        let name: String = self
+            .bin
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("_upt_{name}")
+    }
+
+    fn bash(&self) -> String {
+        let mut lines = vec![
+            format!("{}() {{", self.func_name()),
+            r#"    local cur="${COMP_WORDS[COMP_CWORD]}""#.to_string(),
+            "    if [[ $COMP_CWORD -eq 1 ]]; then".to_string(),
+            format!(
+                r#"        COMPREPLY=($(compgen -W {} -- "$cur"))"#,
+                quote(&self.heads().join(" "))
+            ),
+            "        return".to_string(),
+            "    fi".to_string(),
+            r#"    case "${COMP_WORDS[1]}" in"#.to_string(),
+        ];
+        for (head, words) in self.followed() {
+            lines.push(format!(
+                r#"        {}) COMPREPLY=($(compgen -W {} -- "$cur")) ;;"#,
+                quote(head),
+                quote(&words.join(" "))
+            ));
+        }
+        lines.push("    esac".to_string());
+        lines.push("}".to_string());
+        lines.push(format!("complete -F {} {}", self.func_name(), self.bin));
+        lines.join("\n") + "\n"
+    }
+
+    fn zsh(&self) -> String {
+        let mut lines = vec![
+            format!("{}() {{", self.func_name()),
+            "    if (( CURRENT == 2 )); then".to_string(),
+            format!("        compadd -- {}", quote_all(&self.heads())),
+            "        return".to_string(),
+            "    fi".to_string(),
+            "    case $words[2] in".to_string(),
+        ];
+        for (head, words) in self.followed() {
+            lines.push(format!(
+                "        ({}) compadd -- {} ;;",
+                quote(head),
+                quote_all(words)
+            ));
+        }
+        lines.push("    esac".to_string());
+        lines.push("}".to_string());
+        lines.push(format!("compdef {} {}", self.func_name(), self.bin));
+        lines.join("\n") + "\n"
+    }
+
+    fn fish(&self) -> String {
+        let bin = quote(&self.bin);
+        let mut lines = vec![
+            format!("complete -c {bin} -f"),
+            format!(
+                "complete -c {bin} -n 'test (count (commandline -opc)) -eq 1' -a {}",
+                quote(&self.heads().join(" "))
+            ),
+        ];
+        for (head, words) in self.followed() {
+            lines.push(format!(
+                "complete -c {bin} -n 'test (count (commandline -opc)) -gt 1; and test (commandline -opc)[2] = {}' -a {}",
+                quote(head).replace('\'', "\\'"),
+                quote(&words.join(" "))
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    fn powershell(&self) -> String {
+        let list = |words: &[&str]| {
+            let words: Vec<String> = words.iter().map(|v| quote(v)).collect();
+            format!("@({})", words.join(", "))
+        };
+        let mut lines = vec![
+            format!(
+                "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{",
+                quote(&self.bin)
+            ),
+            "    param($wordToComplete, $commandAst, $cursorPosition)".to_string(),
+            "    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })"
+                .to_string(),
+            "    $candidates = @()".to_string(),
+            "    if ($words.Count -eq 1 -or ($words.Count -eq 2 -and $wordToComplete)) {"
+                .to_string(),
+            format!("        $candidates = {}", list(&self.heads())),
+        ];
+        for (head, words) in self.followed() {
+            let words: Vec<&str> = words.iter().map(|v| v.as_str()).collect();
+            lines.push(format!("    }} elseif ($words[1] -eq {}) {{", quote(head)));
+            lines.push(format!("        $candidates = {}", list(&words)));
+        }
+        lines.push("    }".to_string());
+        lines.push(
+            "    $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {"
+                .to_string(),
+        );
+        lines.push("        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)".to_string());
+        lines.push("    }".to_string());
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn elvish(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "set edit:completion:arg-completer[{}] = {{|@words|",
+                quote(&self.bin)
+            ),
+            "    if (== (count $words) 2) {".to_string(),
+            format!("        put {}", quote_all(&self.heads())),
+            "        return".to_string(),
+            "    }".to_string(),
+        ];
+        for (head, words) in self.followed() {
+            lines.push(format!("    if (eq $words[1] {}) {{", quote(head)));
+            lines.push(format!("        put {}", quote_all(words)));
+            lines.push("    }".to_string());
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Single quote a word, all supported shells treat single quoted text literally.
+///
+/// The words come from the vendor definitions, which never contain single quotes.
+fn quote(v: &str) -> String {
+    format!("'{}'", v)
+}
+
+fn quote_all<T: AsRef<str>>(words: &[T]) -> String {
+    let words: Vec<String> = words.iter().map(|v| quote(v.as_ref())).collect();
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vendor::init_vendor;
+
+    #[test]
+    fn test_completion() {
+        let apt = Completion::new(&init_vendor("apt").unwrap());
+        let bins: Vec<&str> = apt.commands.iter().map(|v| v.bin.as_str()).collect();
+        assert_eq!(bins, ["apt", "apt-get", "apt-cache"]);
+        assert_eq!(
+            apt.commands[2].heads(),
+            ["search", "show"],
+            "apt-cache only searches and shows"
+        );
+        assert_eq!(
+            apt.commands[0].entries[0],
+            (
+                "install".to_string(),
+                vec![
+                    "-y".to_string(),
+                    "--yes".to_string(),
+                    "--assume-yes".to_string(),
+                    "--only-upgrade".to_string()
+                ]
+            )
+        );
+
+        let pacman = Completion::new(&init_vendor("pacman").unwrap());
+        assert_eq!(pacman.commands[0].heads(), ["-S", "-R", "-Q"]);
+        assert_eq!(
+            pacman.commands[0].entries[0].1,
+            ["--noconfirm", "-s", "-i", "-y", "-u"]
+        );
+
+        let urpm = Completion::new(&init_vendor("urpm").unwrap());
+        let bins: Vec<&str> = urpm.commands.iter().map(|v| v.bin.as_str()).collect();
+        assert_eq!(bins, ["urpmi"]);
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut upt = Completion::new(&init_vendor("upt").unwrap());
+        upt.add("upt", "completions", &["bash", "zsh"]);
+        assert_eq!(
+            upt.generate(Shell::Bash),
+            r#"_upt_upt() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W 'install remove uninstall upgrade search info show update list completions' -- "$cur"))
+        return
+    fi
+    case "${COMP_WORDS[1]}" in
+        'install') COMPREPLY=($(compgen -W '-y --yes' -- "$cur")) ;;
+        'remove') COMPREPLY=($(compgen -W '-y --yes' -- "$cur")) ;;
+        'uninstall') COMPREPLY=($(compgen -W '-y --yes' -- "$cur")) ;;
+        'upgrade') COMPREPLY=($(compgen -W '-y --yes' -- "$cur")) ;;
+        'completions') COMPREPLY=($(compgen -W 'bash zsh' -- "$cur")) ;;
+    esac
+}
+complete -F _upt_upt upt
+"#
+        );
+    }
+}
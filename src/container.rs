@@ -0,0 +1,124 @@
+use crate::error::UptError;
+use crate::runner::{query_line, spawn, wants_tty, which_through, Capture, CommandRunner};
+use crate::utils::{remote_os, OS_RELEASE};
+
+use std::io;
+use std::path::PathBuf;
+use std::process::Output;
+use std::str::FromStr;
+
+/// Runs the commands inside a running container, upt does not need to be installed there
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerRunner {
+    /// `podman`, `docker` or `distrobox`
+    engine: String,
+    name: String,
+}
+
+const ENGINES: [&str; 3] = ["podman", "docker", "distrobox"];
+
+impl ContainerRunner {
+    /// Run in the container `name` through the engine, fails if upt does not know the engine
+    pub fn new(engine: &str, name: &str) -> Result<Self, UptError> {
+        if !ENGINES.contains(&engine) {
+            return Err(UptError::InvalidArgs(format!(
+                "Unknown container engine '{}', expected {}.",
+                engine,
+                ENGINES.join(", ")
+            )));
+        }
+        Ok(ContainerRunner {
+            engine: engine.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// The engine's command running argv in the container
+    fn exec_argv(&self, argv: &[String], env: &[(String, String)], tty: bool) -> Vec<String> {
+        let mut exec = vec![self.engine.clone()];
+        if self.engine == "distrobox" {
+            // distrobox sets up the terminal itself and has no option for the environment
+            exec.extend(["enter", &self.name, "--"].map(|v| v.to_string()));
+            if !env.is_empty() {
+                exec.push("env".to_string());
+                exec.extend(env.iter().map(|(k, v)| format!("{k}={v}")));
+            }
+        } else {
+            exec.extend(["exec", "-i"].map(|v| v.to_string()));
+            if tty {
+                exec.push("-t".to_string());
+            }
+            for (k, v) in env {
+                exec.extend(["-e".to_string(), format!("{k}={v}")]);
+            }
+            exec.push(self.name.clone());
+        }
+        exec.extend(argv.iter().cloned());
+        exec
+    }
+
+    /// The exec running the line it is given last with the container's shell
+    fn sh_argv(&self) -> Vec<String> {
+        self.exec_argv(&["sh".to_string(), "-c".to_string()], &[], false)
+    }
+}
+
+/// `[engine:]name`, the engine is podman if it is installed, otherwise docker
+impl FromStr for ContainerRunner {
+    type Err = UptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((engine, name)) => ContainerRunner::new(engine, name),
+            None if which::which("podman").is_ok() => ContainerRunner::new("podman", s),
+            None => ContainerRunner::new("docker", s),
+        }
+    }
+}
+
+impl CommandRunner for ContainerRunner {
+    fn run(
+        &mut self,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Option<&[u8]>,
+        capture: Capture,
+    ) -> io::Result<Output> {
+        let tty = wants_tty(stdin, capture);
+        spawn(&self.exec_argv(argv, env, tty), &[], stdin, capture)
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        self.which_all(&[program]).pop().flatten()
+    }
+
+    /// The programs are looked up with a single exec
+    fn which_all(&self, programs: &[&str]) -> Vec<Option<PathBuf>> {
+        which_through(&self.sh_argv(), programs)
+    }
+
+    fn detect_os(&self) -> Option<String> {
+        remote_os(&query_line(&self.sh_argv(), OS_RELEASE)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_argv() {
+        let argv = ["apt", "install", "vim"].map(|v| v.to_string());
+        let env = [("LANG".to_string(), "C".to_string())];
+        let podman: ContainerRunner = "podman:dev".parse().unwrap();
+        assert_eq!(
+            podman.exec_argv(&argv, &env, true).join(" "),
+            "podman exec -i -t -e LANG=C dev apt install vim"
+        );
+        let distrobox: ContainerRunner = "distrobox:dev".parse().unwrap();
+        assert_eq!(
+            distrobox.exec_argv(&argv, &env, true).join(" "),
+            "distrobox enter dev -- env LANG=C apt install vim"
+        );
+        assert!("lxc:dev".parse::<ContainerRunner>().is_err());
+    }
+}
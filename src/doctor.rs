@@ -0,0 +1,193 @@
+use crate::cache::Detection;
+use crate::error::UptError;
+use crate::runner::{CommandRunner, LocalRunner};
+use crate::utils::parse_os_release;
+use crate::vendor::{init_vendor, os_table, os_tools, select_vendor_with, which_cmd, ACTIONS};
+
+use std::path::PathBuf;
+
+/// Report how upt detects the os and picks the package management tool.
+///
+/// `upt_tool` is the value of `$UPT_TOOL`.
+pub fn doctor(upt_tool: Option<&str>) -> String {
+    let release = std::fs::read_to_string("/etc/os-release").ok();
+    report(
+        &LocalRunner,
+        release.as_deref(),
+        Detection::cached(),
+        upt_tool,
+    )
+}
+
+/// The report of the machine where the runner runs commands, given its os-release and cached detection
+fn report(
+    runner: &dyn CommandRunner,
+    release: Option<&str>,
+    cached: Option<Detection>,
+    upt_tool: Option<&str>,
+) -> String {
+    let mut lines = vec![];
+
+    let os = runner.detect_os().unwrap_or_default();
+    lines.push(format!(
+        "OS: {}",
+        if os.is_empty() { "unknown" } else { &os }
+    ));
+    match release {
+        Some(release) => {
+            lines.push("  /etc/os-release:".to_string());
+            for (k, v) in parse_os_release(release) {
+                lines.push(format!("    {k}={v}"));
+            }
+        }
+        None => lines.push("  /etc/os-release: not found".to_string()),
+    }
+    lines.push(String::new());
+
+    let known = os_table().iter().any(|(v, _)| *v == os);
+    lines.push(format!(
+        "Candidates ({}):",
+        if known {
+            format!("tools of {os}")
+        } else {
+            "unknown os, fall back to common tools".to_string()
+        }
+    ));
+    let candidates: Vec<(&str, &str, Option<PathBuf>)> = os_tools(&os)
+        .into_iter()
+        .map(|(tool, bin_name)| (tool, bin_name, runner.which(bin_name)))
+        .collect();
+    let width = candidates
+        .iter()
+        .map(|(tool, _, _)| tool.len())
+        .max()
+        .unwrap_or_default();
+    for (tool, bin_name, path) in &candidates {
+        lines.push(format!("  {tool:<width$}  {}", located(bin_name, path)));
+    }
+    let detected = candidates.iter().find(|(_, _, path)| path.is_some());
+    match detected {
+        Some((tool, _, _)) => lines.push(format!(
+            "Detected: {tool}, the first candidate that is installed"
+        )),
+        None => lines.push("Detected: none, no candidate is installed".to_string()),
+    }
+    match cached {
+        Some(v) => lines.push(format!("Cached: {} ({})", v.tool, v.bin.display())),
+        None => lines.push("Cached: none".to_string()),
+    }
+    lines.push(String::new());
+
+    let mut chosen = detected.map(|(tool, _, _)| tool.to_string());
+    match upt_tool.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(upt_tool) => {
+            lines.push(format!("UPT_TOOL: {upt_tool}"));
+            let tools: Vec<&str> = upt_tool
+                .split(',')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .collect();
+            let width = tools.iter().map(|v| v.len()).max().unwrap_or_default();
+            for tool in tools {
+                match which_cmd(tool) {
+                    Some(bin_name) => {
+                        let path = runner.which(bin_name);
+                        lines.push(format!("  {tool:<width$}  {}", located(bin_name, &path)));
+                    }
+                    None => lines.push(format!("  {tool:<width$}  not supported")),
+                }
+            }
+            // Chosen like a real run does
+            match select_vendor_with(runner, upt_tool) {
+                Ok(vendor) => {
+                    lines.push(format!(
+                        "Chosen: {}, the first tool of UPT_TOOL that is installed",
+                        vendor.name()
+                    ));
+                    chosen = Some(vendor.name().to_string());
+                }
+                Err(e) => {
+                    lines.push(format!("Chosen: none, {e}"));
+                    chosen = None;
+                }
+            }
+        }
+        None => {
+            lines.push("UPT_TOOL: not set".to_string());
+            match &chosen {
+                Some(tool) => lines.push(format!("Chosen: {tool}, the detected tool")),
+                None => lines.push("Chosen: none".to_string()),
+            }
+        }
+    }
+
+    if let Some(vendor) = chosen.and_then(|v| init_vendor(&v).ok()) {
+        lines.push(String::new());
+        lines.push(format!("Actions of {}:", vendor.name()));
+        let width = ACTIONS.iter().map(|v| v.len()).max().unwrap_or_default();
+        for (name, action) in ACTIONS.iter().zip(vendor.actions()) {
+            let status = match action.cmds().first() {
+                Some(bin_name) => match vendor.locate_with(runner, bin_name) {
+                    Ok(path) => located(bin_name, &Some(path)),
+                    Err(UptError::NoCommand(_, Some(package))) => {
+                        format!("{}, provided by {package}", located(bin_name, &None))
+                    }
+                    Err(_) => located(bin_name, &None),
+                },
+                None => "not supported".to_string(),
+            };
+            lines.push(format!("  {name:<width$}  {status}"));
+        }
+    }
+    lines.join("\n")
+}
+
+fn located(bin_name: &str, path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => format!("{bin_name} => {}", path.display()),
+        None => format!("{bin_name} => not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::RecordingRunner;
+
+    #[test]
+    fn test_report() {
+        let mut runner = RecordingRunner::new();
+        runner.os("fedora");
+        runner.missing("dnf");
+        let release = "ID=fedora\nVERSION_ID=40\n";
+        let text = report(&runner, Some(release), None, None);
+        assert!(text.starts_with("OS: fedora\n  /etc/os-release:\n    ID=fedora\n"));
+        assert!(text.contains("  dnf  dnf => not found\n"));
+        assert!(text.contains("  yum  yum => /usr/bin/yum\n"));
+        assert!(text.contains("Detected: yum, the first candidate that is installed\n"));
+        assert!(text.contains("Cached: none\n"));
+        assert!(text.contains("UPT_TOOL: not set\nChosen: yum, the detected tool\n"));
+        assert!(text.contains("Actions of yum:\n  install         yum => /usr/bin/yum\n"));
+
+        runner.missing("qlist");
+        let text = report(&runner, None, None, Some("dnf, emerge"));
+        assert!(text.contains("  /etc/os-release: not found\n"));
+        assert!(text.contains("Chosen: emerge, the first tool of UPT_TOOL that is installed"));
+        assert!(text.contains(
+            "  list_installed  qlist => not found, provided by app-portage/portage-utils"
+        ));
+
+        // Unsupported and missing tools are skipped like a real run does
+        let text = report(&runner, None, None, Some("foo, dnf, yum"));
+        assert!(text.contains(
+            "  foo  not supported
+"
+        ));
+        assert!(text.contains("Chosen: yum, the first tool of UPT_TOOL that is installed"));
+        runner.missing("yum");
+        let text = report(&runner, None, None, Some("foo, dnf, yum"));
+        assert!(text.contains(
+            "Chosen: none, None of the package management tools is available, tried: foo (not supported), dnf, yum."
+        ));
+    }
+}
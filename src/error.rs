@@ -1,13 +1,29 @@
 use std::error::Error;
 use std::fmt;
 
+/// Errors of upt, their message is meant for the user
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum UptError {
+    /// The named tool is not supported
     NoVendor(String),
+    /// No supported tool is installed
     NoDetectVendor,
+    /// None of the tools listed in `UPT_TOOL` is available
+    NoAvailableVendor(Vec<String>),
+    /// The tool has no command for the task
     InvalidTask,
+    /// The named tool cannot install a specific version
+    NoVersion(String),
+    /// A command is not installed, with the package providing it if known
+    NoCommand(String, Option<String>),
+    /// The action is not known
     InvalidAction(String),
+    /// The arguments are wrong, with the usage to show
     InvalidArgs(String),
+    /// The manifest cannot be read or parsed
+    InvalidManifest(String),
+    /// The help was asked for, it is not a failure
     DisplayHelp(String),
 }
 
@@ -22,9 +38,26 @@ impl fmt::Display for UptError {
                 f,
                 "No package management tool available, use `$UPT_TOOL` to specify one."
             ),
+            NoAvailableVendor(v) => write!(
+                f,
+                "None of the package management tools is available, tried: {}.",
+                v.join(", ")
+            ),
             InvalidTask => write!(f, "The package management tool cannot perform the task."),
+            NoVersion(v) => write!(
+                f,
+                "The package management tool '{}' cannot install a specific version.",
+                v
+            ),
+            NoCommand(v, Some(package)) => write!(
+                f,
+                "Command '{}' not found, install the package '{}' to provide it.",
+                v, package
+            ),
+            NoCommand(v, None) => write!(f, "Command '{}' not found.", v),
             InvalidAction(v) => write!(f, "Invalid action '{}'.", v),
             InvalidArgs(v) => write!(f, "Invalid arguments.\n\n{}", v),
+            InvalidManifest(v) => write!(f, "Invalid manifest, {}", v),
             DisplayHelp(v) => write!(f, "{}", v),
         }
     }
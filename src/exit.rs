@@ -0,0 +1,62 @@
+use crate::error::UptError;
+
+use std::io;
+
+/// Exit codes of upt, the same whichever tool performs the task
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum ExitCode {
+    /// The task was performed
+    Success,
+    /// Any failure not covered by the other codes
+    Failure,
+    /// A package is not known to the tool
+    PackageNotFound,
+    /// The packages are already in the requested state
+    NothingToDo,
+    /// Updates are available, from a check for updates
+    UpdatesAvailable,
+    /// The tool needs more privileges, usually root
+    PermissionDenied,
+    /// The package management tool or a command it needs is not installed
+    ToolMissing,
+}
+
+impl ExitCode {
+    /// The numeric code upt exits with
+    pub fn code(self) -> i32 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::Failure => 1,
+            ExitCode::PackageNotFound => 2,
+            ExitCode::NothingToDo => 3,
+            ExitCode::UpdatesAvailable => 4,
+            ExitCode::PermissionDenied => 5,
+            ExitCode::ToolMissing => 6,
+        }
+    }
+}
+
+impl UptError {
+    /// The code upt exits with on this error
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            UptError::NoDetectVendor | UptError::NoAvailableVendor(_) | UptError::NoCommand(..) => {
+                ExitCode::ToolMissing
+            }
+            UptError::DisplayHelp(_) => ExitCode::Success,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// Exit code of an io error raised when spawning the tool
+impl From<&io::Error> for ExitCode {
+    fn from(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => ExitCode::ToolMissing,
+            io::ErrorKind::PermissionDenied => ExitCode::PermissionDenied,
+            _ => ExitCode::Failure,
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use crate::error::UptError;
+
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// Output format of the listing commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Format {
+    /// Columns aligned with spaces
+    Table,
+    /// A markdown table
+    Markdown,
+    /// An array of objects
+    Json,
+    /// Comma separated values with a header line
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = UptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(UptError::InvalidArgs(format!("Unknown format '{}'.", s))),
+        }
+    }
+}
+
+/// Render rows of cells, `None` cells are shown as `-` in tables, `null` in json.
+///
+/// Each column is a pair of json key and title.
+pub(crate) fn render_table(
+    columns: &[(&str, &str)],
+    rows: &[Vec<Option<String>>],
+    format: Format,
+) -> String {
+    match format {
+        Format::Table | Format::Markdown => {
+            let cell = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, (_, title))| {
+                    rows.iter()
+                        .map(|row| cell(&row[i]).len())
+                        .chain([title.len()])
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let line = |cells: Vec<String>| {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(v, width)| format!("{:<width$}", v, width = width))
+                    .collect();
+                if format == Format::Table {
+                    cells.join("  ").trim_end().to_string()
+                } else {
+                    format!("| {} |", cells.join(" | "))
+                }
+            };
+            let mut lines = vec![line(columns.iter().map(|(_, v)| v.to_string()).collect())];
+            if format == Format::Markdown {
+                lines.push(line(widths.iter().map(|v| "-".repeat(*v)).collect()));
+            }
+            lines.extend(rows.iter().map(|row| line(row.iter().map(cell).collect())));
+            lines.join("\n")
+        }
+        Format::Json => {
+            let lines: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let fields: Vec<String> = columns
+                        .iter()
+                        .zip(row)
+                        .map(|((key, _), v)| format!("{}:{}", json_str(key), json_opt(v)))
+                        .collect();
+                    format!("  {{{}}}", fields.join(","))
+                })
+                .collect();
+            json_lines(lines)
+        }
+        Format::Csv => {
+            let mut lines = vec![columns
+                .iter()
+                .map(|(_, v)| csv_str(v))
+                .collect::<Vec<String>>()
+                .join(",")];
+            lines.extend(rows.iter().map(|row| {
+                row.iter()
+                    .map(|v| csv_str(v.as_deref().unwrap_or_default()))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            }));
+            lines.join("\n")
+        }
+    }
+}
+
+/// Wrap already rendered json values into a json array, one value per line
+pub(crate) fn json_lines(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n{}\n]", lines.join(",\n"))
+}
+
+pub(crate) fn json_str(v: &str) -> String {
+    let mut output = String::from("\"");
+    for c in v.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+pub(crate) fn json_opt(v: &Option<String>) -> String {
+    match v {
+        Some(v) => json_str(v),
+        None => "null".to_string(),
+    }
+}
+
+pub(crate) fn json_array<T: AsRef<str>>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(|v| json_str(v.as_ref())).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// A json value, read from the machine-readable output of some tools
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    /// The number as written
+    Number(String),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a json document, None if it is not valid
+    pub(crate) fn parse(input: &str) -> Option<Json> {
+        let mut chars = input.chars().peekable();
+        let value = Json::value(&mut chars)?;
+        skip_spaces(&mut chars);
+        chars.peek().is_none().then_some(value)
+    }
+
+    fn value(chars: &mut Peekable<Chars>) -> Option<Json> {
+        skip_spaces(chars);
+        match chars.peek()? {
+            '{' => {
+                chars.next();
+                let mut fields = vec![];
+                loop {
+                    skip_spaces(chars);
+                    match chars.next()? {
+                        '}' if fields.is_empty() => break,
+                        '"' => {}
+                        _ => return None,
+                    }
+                    let key = Json::string(chars)?;
+                    skip_spaces(chars);
+                    if chars.next()? != ':' {
+                        return None;
+                    }
+                    fields.push((key, Json::value(chars)?));
+                    skip_spaces(chars);
+                    match chars.next()? {
+                        ',' => {}
+                        '}' => break,
+                        _ => return None,
+                    }
+                }
+                Some(Json::Object(fields))
+            }
+            '[' => {
+                chars.next();
+                let mut items = vec![];
+                skip_spaces(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Some(Json::Array(items));
+                }
+                loop {
+                    items.push(Json::value(chars)?);
+                    skip_spaces(chars);
+                    match chars.next()? {
+                        ',' => {}
+                        ']' => break,
+                        _ => return None,
+                    }
+                }
+                Some(Json::Array(items))
+            }
+            '"' => {
+                chars.next();
+                Json::string(chars).map(Json::Str)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| c.is_ascii_alphanumeric() || "+-.".contains(*c))
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null" => Some(Json::Null),
+                    "true" => Some(Json::Bool(true)),
+                    "false" => Some(Json::Bool(false)),
+                    v if v.parse::<f64>().is_ok() => Some(Json::Number(word)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// The rest of a string whose opening quote is consumed
+    fn string(chars: &mut Peekable<Chars>) -> Option<String> {
+        let mut output = String::new();
+        loop {
+            match chars.next()? {
+                '"' => return Some(output),
+                '\\' => match chars.next()? {
+                    'n' => output.push('\n'),
+                    'r' => output.push('\r'),
+                    't' => output.push('\t'),
+                    'b' => output.push('\u{8}'),
+                    'f' => output.push('\u{c}'),
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        output.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    c => output.push(c),
+                },
+                c => output.push(c),
+            }
+        }
+    }
+
+    /// The value of the key if this is an object
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The items if this is an array, otherwise none
+    pub(crate) fn items(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn csv_str(v: &str) -> String {
+    if v.contains([',', '"', '\n']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_parse() {
+        let json = Json::parse(
+            r#" {"name": "vim", "versions": {"stable": "9.1.0"}, "deps": ["lua", "gettext"],
+                "installed": [], "size": -1.5e3, "head": null, "desc": "Vi \"workalike\" \u00e9"} "#,
+        )
+        .unwrap();
+        assert_eq!(json.get("name").and_then(|v| v.as_str()), Some("vim"));
+        let stable = json.get("versions").and_then(|v| v.get("stable"));
+        assert_eq!(stable.and_then(|v| v.as_str()), Some("9.1.0"));
+        assert_eq!(json.get("deps").unwrap().items().len(), 2);
+        assert!(json.get("installed").unwrap().items().is_empty());
+        assert_eq!(json.get("size"), Some(&Json::Number("-1.5e3".to_string())));
+        assert_eq!(json.get("head"), Some(&Json::Null));
+        assert_eq!(
+            json.get("desc").and_then(|v| v.as_str()),
+            Some("Vi \"workalike\" é")
+        );
+        assert_eq!(Json::parse("[]"), Some(Json::Array(vec![])));
+        assert!(Json::parse("{\"a\": 1,}").is_none());
+        assert!(Json::parse("[1] 2").is_none());
+        assert!(Json::parse("==> vim: stable 9.1.0").is_none());
+    }
+
+    #[test]
+    fn test_render_table() {
+        let columns = [("tool", "Tool"), ("install", "Install")];
+        let rows = vec![
+            vec![
+                Some("apt".to_string()),
+                Some("apt install <pkg>".to_string()),
+            ],
+            vec![Some("x,y".to_string()), None],
+        ];
+        assert_eq!(
+            render_table(&columns, &rows, Format::Markdown),
+            r#"| Tool | Install           |
+| ---- | ----------------- |
+| apt  | apt install <pkg> |
+| x,y  | -                 |"#
+        );
+        assert_eq!(
+            render_table(&columns, &rows, Format::Table),
+            "Tool  Install\napt   apt install <pkg>\nx,y   -"
+        );
+        assert_eq!(
+            render_table(&columns, &rows, Format::Json),
+            r#"[
+  {"tool":"apt","install":"apt install <pkg>"},
+  {"tool":"x,y","install":null}
+]"#
+        );
+        assert_eq!(
+            render_table(&columns, &rows, Format::Csv),
+            "Tool,Install\napt,apt install <pkg>\n\"x,y\","
+        );
+    }
+
+    #[test]
+    fn test_json_str() {
+        assert_eq!(json_str(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(json_str("a\nb\u{1}"), r#""a\nb\u0001""#);
+    }
+}
@@ -0,0 +1,592 @@
+use crate::format::{json_array, json_lines, json_opt, json_str, render_table, Format, Json};
+use crate::installed::split_release;
+
+/// Metadata of a package shown by the `info` action of a tool
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct PackageInfo {
+    /// Name of the package
+    pub name: String,
+    /// Version installed or available
+    pub version: Option<String>,
+    /// Summary of the package, the first paragraph of a longer text
+    pub description: Option<String>,
+    /// Url of the project
+    pub homepage: Option<String>,
+    /// License as the tool names it
+    pub license: Option<String>,
+    /// Installed size as the tool prints it, e.g. `3,746 kB`
+    pub size: Option<String>,
+    /// Names of the packages it depends on, without version constraints
+    pub depends: Vec<String>,
+    /// `None` if the tool does not tell
+    pub installed: Option<bool>,
+}
+
+/// Render the info of the packages as a table, one row per package
+pub fn render_info(infos: &[PackageInfo], format: Format) -> String {
+    let columns = [
+        ("name", "Name"),
+        ("version", "Version"),
+        ("description", "Description"),
+        ("homepage", "Homepage"),
+        ("license", "License"),
+        ("size", "Size"),
+        ("depends", "Depends"),
+        ("installed", "Installed"),
+    ];
+    if format == Format::Json {
+        let objects: Vec<String> = infos
+            .iter()
+            .map(|info| {
+                let installed = info.installed.map(|v| v.to_string());
+                let values = [
+                    json_str(&info.name),
+                    json_opt(&info.version),
+                    json_opt(&info.description),
+                    json_opt(&info.homepage),
+                    json_opt(&info.license),
+                    json_opt(&info.size),
+                    json_array(&info.depends),
+                    installed.unwrap_or_else(|| "null".to_string()),
+                ];
+                let fields: Vec<String> = columns
+                    .iter()
+                    .zip(values)
+                    .map(|((k, _), v)| format!("    {}: {}", json_str(k), v))
+                    .collect();
+                format!("  {{\n{}\n  }}", fields.join(",\n"))
+            })
+            .collect();
+        return json_lines(objects);
+    }
+    let rows: Vec<Vec<Option<String>>> = infos
+        .iter()
+        .map(|info| {
+            // A table cell holds a single line
+            let description = match format {
+                Format::Csv => info.description.clone(),
+                _ => info
+                    .description
+                    .as_ref()
+                    .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" ")),
+            };
+            vec![
+                Some(info.name.clone()),
+                info.version.clone(),
+                description,
+                info.homepage.clone(),
+                info.license.clone(),
+                info.size.clone(),
+                Some(info.depends.join(", ")).filter(|v| !v.is_empty()),
+                info.installed
+                    .map(|v| if v { "yes" } else { "no" }.to_string()),
+            ]
+        })
+        .collect();
+    render_table(&columns, &rows, format)
+}
+
+/// Parse the output of the `info` action of the tool, a record per package or version shown
+pub(crate) fn parse_info(tool: &str, output: &str) -> Vec<PackageInfo> {
+    match tool {
+        "apk" => apk(output),
+        // `apt show` prints APT-Manual-Installed for installed packages only
+        "apt" => key_values(output)
+            .into_iter()
+            .map(|mut info| {
+                info.installed = info.installed.or(Some(false));
+                info
+            })
+            .collect(),
+        "brew" => match Json::parse(output) {
+            Some(json) => brew_json(&json),
+            None => brew(output),
+        },
+        _ => key_values(output),
+    }
+}
+
+/// `Key: value` lines, used by most tools.
+///
+/// An indented line or a line with an empty key continues the value of the previous key,
+/// the name of a package starts the next one.
+fn key_values(output: &str) -> Vec<PackageInfo> {
+    let mut infos = vec![];
+    let mut info = PackageInfo::default();
+    let mut summary: Option<String> = None;
+    // dnf groups the packages under these headers
+    let mut section: Option<bool> = None;
+    let mut field = Field::Other;
+    for line in output.lines() {
+        let trimmed = line.trim();
+        match trimmed {
+            "Installed Packages" => section = Some(true),
+            "Available Packages" => section = Some(false),
+            _ => {}
+        }
+        let (key, value) = match trimmed.split_once(':') {
+            Some((key, value)) if !line.starts_with(char::is_whitespace) || key.is_empty() => {
+                (key.trim().to_lowercase(), value.trim())
+            }
+            _ => ("".to_string(), trimmed),
+        };
+        if trimmed.is_empty() {
+            continue;
+        }
+        // Only indented lines continue a value, others such as dnf's headers end it
+        if key.is_empty() && !line.starts_with(char::is_whitespace) && !trimmed.starts_with(':') {
+            field = Field::Other;
+        }
+        if !key.is_empty() {
+            field = Field::of(&key);
+            if matches!(field, Field::Name | Field::NameVersion) {
+                if !info.name.is_empty() {
+                    infos.push(finish(info, summary.take()));
+                    info = PackageInfo::default();
+                }
+                info.installed = section;
+            }
+        }
+        let continued = key.is_empty();
+        match field {
+            Field::Name if !continued => info.name = value.to_string(),
+            Field::NameVersion if !continued => {
+                if let Some((name, version)) = value.rsplit_once('-') {
+                    info.name = name.to_string();
+                    info.version = Some(version.to_string());
+                }
+            }
+            Field::Version => set(&mut info.version, value),
+            Field::Summary => set(&mut summary, value),
+            Field::Description => append(&mut info.description, value),
+            Field::Homepage => set(&mut info.homepage, value),
+            Field::License => set(&mut info.license, value),
+            Field::Size => set(&mut info.size, value),
+            Field::Depends => info.depends.extend(depends(value)),
+            Field::Installed if !continued => {
+                let value = value.to_lowercase();
+                info.installed = Some(
+                    key == "apt-manual-installed"
+                        || value.ends_with(" installed")
+                        || (value != "no" && key == "installed"),
+                );
+            }
+            _ => {}
+        }
+    }
+    if !info.name.is_empty() {
+        infos.push(finish(info, summary));
+    }
+    infos
+}
+
+/// The summary describes the package if the tool has no longer description
+fn finish(mut info: PackageInfo, summary: Option<String>) -> PackageInfo {
+    if info.description.is_none() {
+        info.description = summary;
+    }
+    info
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    /// `vim-9.0_1`
+    NameVersion,
+    Version,
+    Summary,
+    Description,
+    Homepage,
+    License,
+    Size,
+    Depends,
+    Installed,
+    Other,
+}
+
+impl Field {
+    fn of(key: &str) -> Self {
+        match key {
+            "name" | "package" | "pkgname" => Field::Name,
+            "pkgver" => Field::NameVersion,
+            "version" => Field::Version,
+            "summary" | "short_desc" | "comment" => Field::Summary,
+            "description" => Field::Description,
+            "homepage" | "url" | "webpage" | "upstream url" | "website" | "www" => Field::Homepage,
+            "license" | "licenses" => Field::License,
+            "installed-size" | "installed size" | "installed_size" | "size" | "flat size" => {
+                Field::Size
+            }
+            "depends" | "depends on" | "requires" | "dependencies" | "run_depends" => {
+                Field::Depends
+            }
+            "installed" | "status" | "apt-manual-installed" => Field::Installed,
+            _ => Field::Other,
+        }
+    }
+}
+
+/// Set the field unless it has a value, a value continued on the next lines keeps its first line
+fn set(field: &mut Option<String>, value: &str) {
+    if field.is_none() && !value.is_empty() {
+        *field = Some(value.to_string());
+    }
+}
+
+/// Add a line to the field, a `.` line is an empty line as in Debian's descriptions
+fn append(field: &mut Option<String>, value: &str) {
+    let value = if value == "." { "" } else { value };
+    match field {
+        Some(v) => {
+            v.push('\n');
+            v.push_str(value);
+        }
+        None if !value.is_empty() => *field = Some(value.to_string()),
+        None => {}
+    }
+}
+
+/// Package names of a dependency list, e.g. `libc6 (>= 2.34), vim-common` or `glibc>=2.36  acl`
+fn depends(value: &str) -> Vec<String> {
+    let items: Vec<&str> = if value.contains(',') {
+        value.split(',').collect()
+    } else {
+        value.split_whitespace().collect()
+    };
+    items
+        .into_iter()
+        .map(|v| {
+            let v = v.trim();
+            v.split(|c: char| c.is_whitespace() || "(<>=".contains(c))
+                .next()
+                .unwrap_or(v)
+                .to_string()
+        })
+        .filter(|v| !v.is_empty() && v != "None")
+        .collect()
+}
+
+/// Sections of `apk info`
+///
+/// ```text
+/// vim-9.0.2073-r0 description:
+/// Improved vi-style text editor
+///
+/// vim-9.0.2073-r0 webpage:
+/// https://www.vim.org/
+/// ```
+fn apk(output: &str) -> Vec<PackageInfo> {
+    let mut infos: Vec<PackageInfo> = vec![];
+    let mut field = Field::Other;
+    for line in output.lines().map(|v| v.trim()) {
+        if line.is_empty() {
+            field = Field::Other;
+            continue;
+        }
+        let header = line
+            .strip_suffix(':')
+            .and_then(|v| v.split_once(' '))
+            .and_then(|(pkg, key)| Some((split_release(pkg)?, key)));
+        if let Some(((name, version), key)) = header {
+            if infos.last().map(|v| v.name.as_str()) != Some(name) {
+                infos.push(PackageInfo {
+                    name: name.to_string(),
+                    version: Some(version),
+                    ..Default::default()
+                });
+            }
+            field = Field::of(key);
+            continue;
+        }
+        let Some(info) = infos.last_mut() else {
+            continue;
+        };
+        match field {
+            Field::Description | Field::Summary => append(&mut info.description, line),
+            Field::Homepage => set(&mut info.homepage, line),
+            Field::License => set(&mut info.license, line),
+            Field::Size => set(&mut info.size, line),
+            Field::Depends => info.depends.extend(depends(line)),
+            _ => {}
+        }
+    }
+    infos
+}
+
+/// Text of `brew info`, used when it does not print json
+///
+/// ```text
+/// ==> vim: stable 9.1.0 (bottled), HEAD
+/// Vi 'workalike' with many additional features
+/// https://www.vim.org/
+/// Not installed
+/// License: Vim
+/// ==> Dependencies
+/// Required: gettext ✘, lua ✘
+/// ```
+fn brew(output: &str) -> Vec<PackageInfo> {
+    let mut infos: Vec<PackageInfo> = vec![];
+    // The lines after the package's header, the dependencies or another section such as caveats
+    let mut section = "";
+    for line in output.lines().map(|v| v.trim()) {
+        if let Some((name, rest)) = line.strip_prefix("==> ").and_then(|v| v.split_once(": ")) {
+            infos.push(PackageInfo {
+                name: name.to_string(),
+                version: rest
+                    .strip_prefix("stable ")
+                    .and_then(|v| v.split_whitespace().next())
+                    .map(|v| v.trim_end_matches(',').to_string()),
+                ..Default::default()
+            });
+            section = "header";
+            continue;
+        }
+        if let Some(title) = line.strip_prefix("==> ") {
+            section = if title == "Dependencies" {
+                "dependencies"
+            } else {
+                "other"
+            };
+            continue;
+        }
+        let Some(info) = infos.last_mut() else {
+            continue;
+        };
+        match section {
+            "header" if info.description.is_none() => info.description = Some(line.to_string()),
+            "header" => {
+                if line.starts_with("https://") || line.starts_with("http://") {
+                    set(&mut info.homepage, line);
+                } else if line == "Not installed" {
+                    info.installed = Some(false);
+                } else if line.starts_with("Installed") || line.starts_with('/') {
+                    info.installed = info.installed.or(Some(true));
+                } else if let Some(v) = line.strip_prefix("License: ") {
+                    set(&mut info.license, v);
+                }
+            }
+            "dependencies" => {
+                if let Some(v) = line.strip_prefix("Required: ") {
+                    info.depends.extend(depends(v));
+                }
+            }
+            _ => {}
+        }
+    }
+    infos
+}
+
+/// `brew info --json=v2`, formulae and casks
+fn brew_json(json: &Json) -> Vec<PackageInfo> {
+    let text = |v: Option<&Json>| v.and_then(|v| v.as_str()).map(|v| v.to_string());
+    let formulae = json.get("formulae").map(|v| v.items()).unwrap_or_default();
+    let casks = json.get("casks").map(|v| v.items()).unwrap_or_default();
+    let mut infos: Vec<PackageInfo> = formulae
+        .iter()
+        .map(|v| PackageInfo {
+            name: text(v.get("name")).unwrap_or_default(),
+            version: text(v.get("versions").and_then(|v| v.get("stable"))),
+            description: text(v.get("desc")),
+            homepage: text(v.get("homepage")),
+            license: text(v.get("license")),
+            size: None,
+            depends: v
+                .get("dependencies")
+                .map(|v| v.items())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                .collect(),
+            installed: Some(v.get("installed").is_some_and(|v| !v.items().is_empty())),
+        })
+        .collect();
+    infos.extend(casks.iter().map(|v| PackageInfo {
+        name: text(v.get("token")).unwrap_or_default(),
+        version: text(v.get("version")),
+        description: text(v.get("desc")),
+        homepage: text(v.get("homepage")),
+        installed: Some(text(v.get("installed")).is_some()),
+        ..Default::default()
+    }));
+    infos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let infos = parse_info(
+            "apt",
+            r#"Package: vim
+Version: 2:9.0.1378-2
+Priority: optional
+Installed-Size: 3,746 kB
+Depends: vim-common (= 2:9.0.1378-2), vim-runtime (= 2:9.0.1378-2), libacl1 (>= 2.2.23)
+Homepage: https://www.vim.org/
+APT-Manual-Installed: yes
+Description: Vi IMproved - enhanced vi editor
+ Vim is an almost compatible version of the UNIX editor Vi.
+ .
+ It adds many features.
+
+Package: vim
+Version: 2:9.0.1378-1
+"#,
+        );
+        // Every version shown is a record
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[1].version.as_deref(), Some("2:9.0.1378-1"));
+        assert_eq!(infos[1].installed, Some(false));
+        assert_eq!(
+            infos[0],
+            PackageInfo {
+                name: "vim".to_string(),
+                version: Some("2:9.0.1378-2".to_string()),
+                description: Some(
+                    "Vi IMproved - enhanced vi editor\nVim is an almost compatible version of the UNIX editor Vi.\n\nIt adds many features."
+                        .to_string()
+                ),
+                homepage: Some("https://www.vim.org/".to_string()),
+                license: None,
+                size: Some("3,746 kB".to_string()),
+                depends: vec![
+                    "vim-common".to_string(),
+                    "vim-runtime".to_string(),
+                    "libacl1".to_string()
+                ],
+                installed: Some(true),
+            }
+        );
+
+        let infos = parse_info(
+            "dnf",
+            r#"Installed Packages
+Name         : vim-enhanced
+Version      : 9.0.2081
+Size         : 4.0 M
+Summary      : A version of the VIM editor which includes recent enhancements
+URL          : http://www.vim.org/
+License      : Vim and MIT
+Description  : VIM (VIsual editor iMproved) is an updated and improved version of the
+             : vi editor.
+
+Available Packages
+Name         : jq
+Version      : 1.7.1
+Summary      : Command-line JSON processor
+"#,
+        );
+        assert_eq!(infos[0].name, "vim-enhanced");
+        assert_eq!(infos[0].installed, Some(true));
+        assert_eq!(
+            infos[0].description.as_deref(),
+            Some("VIM (VIsual editor iMproved) is an updated and improved version of the\nvi editor.")
+        );
+        assert_eq!(infos[0].license.as_deref(), Some("Vim and MIT"));
+        // The summary stands for a missing description
+        assert_eq!(infos[1].name, "jq");
+        assert_eq!(infos[1].installed, Some(false));
+        assert_eq!(
+            infos[1].description.as_deref(),
+            Some("Command-line JSON processor")
+        );
+
+        let info = &parse_info(
+            "pacman",
+            "Repository      : extra\nName            : vim\nVersion         : 9.1.0-1\nDepends On      : vim-runtime=9.1.0-1  gpm  acl\n",
+        )[0];
+        assert_eq!(info.depends, ["vim-runtime", "gpm", "acl"]);
+        assert_eq!(info.installed, None);
+
+        let info = &parse_info(
+            "zypper",
+            "Information for package vim:\n-----------------------------\nName           : vim\nInstalled      : No\nDescription    :\n    Vim is a text editor.\n",
+        )[0];
+        assert_eq!(info.installed, Some(false));
+        assert_eq!(info.description.as_deref(), Some("Vim is a text editor."));
+
+        let info = &parse_info(
+            "xbps",
+            "pkgver: vim-9.0.2116_1\nrun_depends:\n\tglibc>=2.36_1\n\tvim-common>=9.0_1\nshort_desc: Vim editor\n",
+        )[0];
+        assert_eq!(info.name, "vim");
+        assert_eq!(info.version.as_deref(), Some("9.0.2116_1"));
+        assert_eq!(info.depends, ["glibc", "vim-common"]);
+
+        let info = &parse_info(
+            "apk",
+            "vim-9.0.2073-r0 description:\nImproved vi-style text editor\n\nvim-9.0.2073-r0 webpage:\nhttps://www.vim.org/\n\nvim-9.0.2073-r0 installed size:\n3388 KiB\n",
+        )[0];
+        assert_eq!(info.name, "vim");
+        assert_eq!(info.version.as_deref(), Some("9.0.2073-r0"));
+        assert_eq!(info.homepage.as_deref(), Some("https://www.vim.org/"));
+        assert_eq!(info.size.as_deref(), Some("3388 KiB"));
+
+        let info = &parse_info(
+            "brew",
+            "==> vim: stable 9.1.0 (bottled), HEAD\nVi 'workalike' with many additional features\nhttps://www.vim.org/\nNot installed\nLicense: Vim\n==> Dependencies\nRequired: gettext ✘, lua ✘\n==> Caveats\n/opt/homebrew/etc/vimrc is read\n==> jq: stable 1.7.1 (bottled)\nLightweight and flexible command-line JSON processor\nInstalled\n",
+        );
+        assert_eq!(info[0].version.as_deref(), Some("9.1.0"));
+        assert_eq!(info[0].installed, Some(false));
+        assert_eq!(info[0].depends, ["gettext", "lua"]);
+        assert_eq!(info[1].name, "jq");
+        assert_eq!(info[1].installed, Some(true));
+
+        let info = parse_info(
+            "brew",
+            r#"{"formulae": [{"name": "vim", "desc": "Vi 'workalike' with many additional features",
+                "license": "Vim", "homepage": "https://www.vim.org/", "versions": {"stable": "9.1.0"},
+                "dependencies": ["gettext", "lua"], "installed": [{"version": "9.1.0"}]}],
+              "casks": [{"token": "firefox", "version": "126.0", "desc": "Web browser",
+                "homepage": "https://www.mozilla.org/firefox/", "installed": null}]}"#,
+        );
+        assert_eq!(
+            info[0],
+            PackageInfo {
+                name: "vim".to_string(),
+                version: Some("9.1.0".to_string()),
+                description: Some("Vi 'workalike' with many additional features".to_string()),
+                homepage: Some("https://www.vim.org/".to_string()),
+                license: Some("Vim".to_string()),
+                size: None,
+                depends: vec!["gettext".to_string(), "lua".to_string()],
+                installed: Some(true),
+            }
+        );
+        assert_eq!(info[1].name, "firefox");
+        assert_eq!(info[1].installed, Some(false));
+    }
+
+    #[test]
+    fn test_render_info() {
+        let info = PackageInfo {
+            name: "vim".to_string(),
+            depends: vec!["libc".to_string()],
+            installed: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_info(std::slice::from_ref(&info), Format::Json),
+            r#"[
+  {
+    "name": "vim",
+    "version": null,
+    "description": null,
+    "homepage": null,
+    "license": null,
+    "size": null,
+    "depends": ["libc"],
+    "installed": true
+  }
+]"#
+        );
+        let info = PackageInfo {
+            description: Some("Vi IMproved\nA text editor".to_string()),
+            ..info
+        };
+        assert!(render_info(&[info], Format::Table).contains("Vi IMproved A text editor"));
+    }
+}
@@ -0,0 +1,549 @@
+use crate::format::{render_table, Format};
+
+/// A package reported by the `list_installed` action of a tool
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct InstalledPackage {
+    /// Name of the package
+    pub name: String,
+    /// Version installed
+    pub version: Option<String>,
+    /// Architecture, e.g. `amd64`
+    pub arch: Option<String>,
+    /// The repository or source the package was installed from
+    pub repo: Option<String>,
+}
+
+impl InstalledPackage {
+    fn new(name: &str, version: Option<&str>) -> Self {
+        InstalledPackage {
+            name: name.to_string(),
+            version: version.map(|v| v.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Render the packages as a table of name, version, arch and repo
+pub fn render_installed(pkgs: &[InstalledPackage], format: Format) -> String {
+    let columns = [
+        ("name", "Name"),
+        ("version", "Version"),
+        ("arch", "Arch"),
+        ("repo", "Repo"),
+    ];
+    let rows: Vec<Vec<Option<String>>> = pkgs
+        .iter()
+        .map(|v| {
+            vec![
+                Some(v.name.clone()),
+                v.version.clone(),
+                v.arch.clone(),
+                v.repo.clone(),
+            ]
+        })
+        .collect();
+    render_table(&columns, &rows, format)
+}
+
+/// Parse the output of the `list_installed` action of the tool
+pub(crate) fn parse_installed(tool: &str, output: &str) -> Vec<InstalledPackage> {
+    let lines = output
+        .lines()
+        .map(|v| v.trim_end())
+        .filter(|v| !v.is_empty());
+    match tool {
+        "apk" => lines.filter_map(apk_line).collect(),
+        "apt" | "pkg(termux)" => lines.filter_map(apt_line).collect(),
+        "dnf" | "yum" => dnf(lines),
+        "emerge" | "nix-env" => lines
+            .map(|v| {
+                let (name, version) = split_version(v.trim());
+                InstalledPackage::new(name, version)
+            })
+            .collect(),
+        "brew" => lines
+            .filter_map(|v| {
+                // Every installed version is listed, the last one is linked
+                let mut fields = v.split_whitespace();
+                Some(InstalledPackage::new(fields.next()?, fields.last()))
+            })
+            .collect(),
+        // Name, application id, version, branch and installation, separated by tabs when piped
+        "flatpak" => lines
+            .filter_map(|v| {
+                let fields: Vec<&str> = v.split('\t').collect();
+                let version = fields.get(2).copied().filter(|v| !v.is_empty());
+                Some(InstalledPackage::new(fields.get(1)?, version))
+            })
+            .collect(),
+        "guix" => lines
+            .filter_map(|v| {
+                let mut fields = v.split('\t');
+                Some(InstalledPackage::new(fields.next()?, fields.next()))
+            })
+            .collect(),
+        "opkg" => lines
+            .filter_map(|v| {
+                let (name, version) = v.split_once(" - ")?;
+                Some(InstalledPackage::new(name, Some(version)))
+            })
+            .collect(),
+        "pkg" => lines
+            .filter_map(|v| {
+                let (name, version) = v.split_whitespace().next()?.rsplit_once('-')?;
+                Some(InstalledPackage::new(name, Some(version)))
+            })
+            .collect(),
+        "port" => lines
+            .filter(|v| v.starts_with(' '))
+            .filter_map(|v| {
+                let mut fields = v.split_whitespace();
+                let name = fields.next()?;
+                let version = fields.next().map(|v| {
+                    let v = v.trim_start_matches('@');
+                    v.split_once('+').map(|(v, _)| v).unwrap_or(v)
+                });
+                Some(InstalledPackage::new(name, version))
+            })
+            .collect(),
+        "urpm" => lines.map(rpm_line).collect(),
+        "scoop" => table(lines, &["Name", "Version", "Source"])
+            .into_iter()
+            .map(|row| {
+                let mut pkg = InstalledPackage::new(&row[0], Some(&row[1]));
+                pkg.repo = Some(row[2].clone()).filter(|v| !v.is_empty());
+                pkg
+            })
+            .collect(),
+        "winget" => table(lines, &["Id", "Version", "Source"])
+            .into_iter()
+            .map(|row| {
+                let mut pkg = InstalledPackage::new(&row[0], Some(&row[1]));
+                pkg.repo = Some(row[2].clone()).filter(|v| !v.is_empty());
+                pkg
+            })
+            .collect(),
+        "slackpkg" => lines.filter_map(slackware_package).collect(),
+        "snap" => lines
+            .skip(1)
+            .filter_map(|v| {
+                let mut fields = v.split_whitespace();
+                Some(InstalledPackage::new(fields.next()?, fields.next()))
+            })
+            .collect(),
+        "xbps" => lines
+            .filter_map(|v| {
+                let (name, version) = v.split_whitespace().nth(1)?.rsplit_once('-')?;
+                Some(InstalledPackage::new(name, Some(version)))
+            })
+            .collect(),
+        "choco" => lines
+            .filter(|v| !v.starts_with("Chocolatey v") && !v.ends_with(" packages installed."))
+            .filter_map(|v| {
+                let mut fields = v.split_whitespace();
+                Some(InstalledPackage::new(fields.next()?, fields.next()))
+            })
+            .collect(),
+        "zypper" => lines
+            .filter_map(|v| {
+                let fields: Vec<&str> = v.split('|').map(|v| v.trim()).collect();
+                match fields.get(1) {
+                    Some(name) if *name != "Name" => Some(InstalledPackage::new(name, None)),
+                    _ => None,
+                }
+            })
+            .collect(),
+        // Only names are listed
+        "eopkg" | "prt-get" => lines
+            .filter_map(|v| Some(InstalledPackage::new(v.split_whitespace().next()?, None)))
+            .collect(),
+        _ => lines
+            .filter(|v| v.starts_with(|c: char| c.is_alphanumeric()))
+            .filter_map(|v| {
+                let mut fields = v.split_whitespace();
+                Some(InstalledPackage::new(fields.next()?, fields.next()))
+            })
+            .collect(),
+    }
+}
+
+/// `bash-5.2.015-x86_64-1`, name-version-arch-build
+pub(crate) fn slackware_package(v: &str) -> Option<InstalledPackage> {
+    let mut parts = v.trim().rsplitn(4, '-');
+    let _build = parts.next()?;
+    let arch = parts.next()?;
+    let version = parts.next()?;
+    let name = parts.next()?;
+    let mut pkg = InstalledPackage::new(name, Some(version));
+    pkg.arch = Some(arch.to_string());
+    Some(pkg)
+}
+
+/// `musl-1.2.4-r2 x86_64 {musl} (MIT) [installed]`
+fn apk_line(line: &str) -> Option<InstalledPackage> {
+    let mut fields = line.split_whitespace();
+    let (name, version) = split_release(fields.next()?)?;
+    let mut pkg = InstalledPackage::new(name, Some(&version));
+    pkg.arch = fields.next().map(|v| v.to_string());
+    Some(pkg)
+}
+
+/// `vim/stable,now 2:9.0.1378-2 amd64 [installed]`
+fn apt_line(line: &str) -> Option<InstalledPackage> {
+    let mut fields = line.split_whitespace();
+    let (name, repo) = fields.next()?.split_once('/')?;
+    let mut pkg = InstalledPackage::new(name, fields.next());
+    pkg.arch = fields.next().map(|v| v.to_string());
+    pkg.repo = repo.split(',').next().map(|v| v.to_string());
+    Some(pkg)
+}
+
+/// `vim-enhanced.x86_64    2:9.0.2081-1.fc39    @updates`, a long name pushes the rest to the next line
+fn dnf<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<InstalledPackage> {
+    let mut pkgs = vec![];
+    let mut pending: Option<String> = None;
+    for line in lines {
+        let line = match pending.take() {
+            Some(v) => format!("{v} {line}"),
+            None => line.to_string(),
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [_] if !line.ends_with(':') && line.contains('.') => pending = Some(line),
+            [name_arch, version, repo, ..] => {
+                let Some((name, arch)) = name_arch.rsplit_once('.') else {
+                    continue;
+                };
+                let mut pkg = InstalledPackage::new(name, Some(version));
+                pkg.arch = Some(arch.to_string());
+                pkg.repo = Some(repo.trim_start_matches('@').to_string());
+                pkgs.push(pkg);
+            }
+            _ => {}
+        }
+    }
+    pkgs
+}
+
+/// `bash-5.2.15-1.mga9.x86_64`, the default format of `rpm -qa`
+fn rpm_line(line: &str) -> InstalledPackage {
+    let line = line.trim();
+    let (nvr, arch) = match line.rsplit_once('.') {
+        Some((nvr, arch)) if RPM_ARCHS.contains(&arch) => (nvr, Some(arch)),
+        _ => (line, None),
+    };
+    let mut pkg = match split_release(nvr) {
+        Some((name, version)) => InstalledPackage::new(name, Some(&version)),
+        None => InstalledPackage::new(nvr, None),
+    };
+    pkg.arch = arch.map(|v| v.to_string());
+    pkg
+}
+
+/// Split `name-version-release` into the name and `version-release`
+pub(crate) fn split_release(v: &str) -> Option<(&str, String)> {
+    let mut parts = v.rsplitn(3, '-');
+    let release = parts.next()?;
+    let version = parts.next()?;
+    let name = parts.next()?;
+    Some((name, format!("{version}-{release}")))
+}
+
+pub(crate) const RPM_ARCHS: [&str; 9] = [
+    "x86_64", "i386", "i586", "i686", "noarch", "aarch64", "armv7hl", "ppc64le", "s390x",
+];
+
+/// Split `category/name-1.2.3-r1` at the first dash followed by a digit
+pub(crate) fn split_version(v: &str) -> (&str, Option<&str>) {
+    let mut offset = 0;
+    for part in v.split('-') {
+        if offset > 0 && part.starts_with(|c: char| c.is_ascii_digit()) {
+            return (&v[..offset - 1], Some(&v[offset..]));
+        }
+        offset += part.len() + 1;
+    }
+    (v, None)
+}
+
+/// Cells of the columns of a table whose header is underlined with dashes
+pub(crate) fn table<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    columns: &[&str],
+) -> Vec<Vec<String>> {
+    let mut header: Option<&str> = None;
+    let mut offsets: Vec<(usize, Option<usize>)> = vec![];
+    let mut rows = vec![];
+    for line in lines {
+        if !offsets.is_empty() {
+            let chars: Vec<char> = line.chars().collect();
+            let cell = |(start, end): &(usize, Option<usize>)| {
+                let end = end.unwrap_or(chars.len()).min(chars.len());
+                let start = (*start).min(end);
+                chars[start..end]
+                    .iter()
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            };
+            rows.push(offsets.iter().map(cell).collect());
+        } else if line.trim_start().starts_with("---") {
+            let Some(header) = header else {
+                continue;
+            };
+            // Column titles are separated by spaces, a cell ends where the next title starts
+            let chars: Vec<char> = header.chars().collect();
+            let starts: Vec<(usize, &str)> = (0..chars.len())
+                .filter(|i| !chars[*i].is_whitespace() && (*i == 0 || chars[i - 1].is_whitespace()))
+                .zip(header.split_whitespace())
+                .collect();
+            for column in columns {
+                let i = starts.iter().position(|(_, v)| v == column);
+                let range = match i {
+                    Some(i) => (starts[i].0, starts.get(i + 1).map(|(v, _)| *v)),
+                    None => (usize::MAX, Some(usize::MAX)),
+                };
+                offsets.push(range);
+            }
+        } else {
+            header = Some(line);
+        }
+    }
+    rows
+}
+
+/// Parse the output of the tool's query of explicitly installed packages, see `Vendor::explicit_query`
+pub(crate) fn parse_explicit(tool: &str, output: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for line in output.lines().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        let name = match tool {
+            // /etc/apk/world holds constraints, e.g. `vim=9.0.2127-r0`
+            "apk" => line.split(['=', '<', '>', '~']).next(),
+            // vim-9.0.2116_1
+            "xbps" => line.rsplit_once('-').map(|(name, _)| name),
+            _ => line.split_whitespace().next(),
+        };
+        if let Some(name) = name {
+            if !names.iter().any(|v| v == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Parse the output of the tool's query of the packages the os needs, see `Vendor::essential_query`
+pub(crate) fn parse_essential(tool: &str, output: &str) -> Vec<String> {
+    let lines = output.lines().map(|v| v.trim());
+    match tool {
+        // `bash yes required`, the essential flag may be empty
+        "apt" | "nala" | "pkg(termux)" => lines
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let name = words.next()?;
+                words
+                    .any(|v| matches!(v, "yes" | "required" | "important"))
+                    .then(|| name.to_string())
+            })
+            .collect(),
+        // protected.d files list a package per line
+        _ => lines
+            .filter(|v| !v.is_empty() && !v.starts_with('#'))
+            .filter_map(|v| v.split_whitespace().next())
+            .map(|v| v.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkg(name: &str, version: &str, arch: Option<&str>, repo: Option<&str>) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            arch: arch.map(|v| v.to_string()),
+            repo: repo.map(|v| v.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_installed() {
+        assert_eq!(
+            parse_installed(
+                "apt",
+                "Listing...\nvim/stable,now 2:9.0.1378-2 amd64 [installed]\n"
+            ),
+            [pkg("vim", "2:9.0.1378-2", Some("amd64"), Some("stable"))]
+        );
+        assert_eq!(
+            parse_installed("apk", "musl-1.2.4-r2 x86_64 {musl} (MIT) [installed]\n"),
+            [pkg("musl", "1.2.4-r2", Some("x86_64"), None)]
+        );
+        assert_eq!(
+            parse_installed("pacman", "vim 9.0.2153-1\n"),
+            [pkg("vim", "9.0.2153-1", None, None)]
+        );
+        assert_eq!(
+            parse_installed(
+                "dnf",
+                "Installed Packages\nvim-enhanced.x86_64   2:9.0.2081-1.fc39   @updates\nvery-long-package-name.noarch\n    1.0-1.fc39   @fedora\n"
+            ),
+            [
+                pkg("vim-enhanced", "2:9.0.2081-1.fc39", Some("x86_64"), Some("updates")),
+                pkg("very-long-package-name", "1.0-1.fc39", Some("noarch"), Some("fedora"))
+            ]
+        );
+        assert_eq!(
+            parse_installed("urpm", "bash-5.2.15-1.mga9.x86_64\n"),
+            [pkg("bash", "5.2.15-1.mga9", Some("x86_64"), None)]
+        );
+        assert_eq!(
+            parse_installed("emerge", "app-editors/vim-9.0.2167-r1\n"),
+            [pkg("app-editors/vim", "9.0.2167-r1", None, None)]
+        );
+        assert_eq!(
+            parse_installed("slackpkg", "bash-5.2.015-x86_64-1\n"),
+            [pkg("bash", "5.2.015", Some("x86_64"), None)]
+        );
+        assert_eq!(
+            parse_installed("xbps", "ii vim-9.0.2116_1  Vim editor\n"),
+            [pkg("vim", "9.0.2116_1", None, None)]
+        );
+        assert_eq!(
+            parse_installed(
+                "scoop",
+                "Installed apps:\n\nName Version Source Updated             Info\n---- ------- ------ -------             ----\ngit  2.43.0  main   2024-01-01 12:00:00\n"
+            ),
+            [pkg("git", "2.43.0", None, Some("main"))]
+        );
+        assert_eq!(
+            parse_installed("brew", "git 2.45.1\njq 1.6 1.7.1\n"),
+            [
+                pkg("git", "2.45.1", None, None),
+                pkg("jq", "1.7.1", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "flatpak",
+                "Firefox\torg.mozilla.firefox\t126.0\tstable\tsystem\nGNOME Platform\torg.gnome.Platform\t\t46\tsystem\n"
+            ),
+            [
+                pkg("org.mozilla.firefox", "126.0", None, None),
+                InstalledPackage::new("org.gnome.Platform", None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "guix",
+                "hello\t2.12.1\tout\t/gnu/store/1ljz0bq1b2q3s-hello-2.12.1\n"
+            ),
+            [pkg("hello", "2.12.1", None, None)]
+        );
+        assert_eq!(
+            parse_installed("opkg", "busybox - 1.36.1-1\nlibc - 1.2.4-4\n"),
+            [
+                pkg("busybox", "1.36.1-1", None, None),
+                pkg("libc", "1.2.4-4", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "pkg",
+                "curl-8.7.1                     Command line tool for transferring data\npy39-setuptools-63.1.0         Python packages installer\n"
+            ),
+            [
+                pkg("curl", "8.7.1", None, None),
+                pkg("py39-setuptools", "63.1.0", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "port",
+                "The following ports are currently installed:\n  curl @8.7.1_0+ssl (active)\n  zlib @1.3.1_0 (active)\n"
+            ),
+            [
+                pkg("curl", "8.7.1_0", None, None),
+                pkg("zlib", "1.3.1_0", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "winget",
+                "Name            Id              Version        Available Source\n---------------------------------------------------------------\nGit             Git.Git         2.45.1         2.45.2    winget\nMicrosoft Edge  Microsoft.Edge  125.0.2535.67\n"
+            ),
+            [
+                pkg("Git.Git", "2.45.1", None, Some("winget")),
+                pkg("Microsoft.Edge", "125.0.2535.67", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "snap",
+                "Name    Version   Rev    Tracking       Publisher   Notes\ncore22  20240408  1380   latest/stable  canonical✓  base\n"
+            ),
+            [pkg("core22", "20240408", None, None)]
+        );
+        assert_eq!(
+            parse_installed(
+                "choco",
+                "Chocolatey v2.2.2\nchocolatey 2.2.2\ngit 2.45.1\n2 packages installed.\n"
+            ),
+            [
+                pkg("chocolatey", "2.2.2", None, None),
+                pkg("git", "2.45.1", None, None)
+            ]
+        );
+        assert_eq!(
+            parse_installed(
+                "zypper",
+                "Loading repository data...\nReading installed packages...\n\nS  | Name | Summary     | Type\n---+------+-------------+--------\ni+ | vim  | Vi IMproved | package\n"
+            ),
+            [InstalledPackage::new("vim", None)]
+        );
+    }
+
+    #[test]
+    fn test_split_version() {
+        assert_eq!(split_version("hello-2.12.1"), ("hello", Some("2.12.1")));
+        assert_eq!(
+            split_version("x11-libs/gtk+-3.24.41"),
+            ("x11-libs/gtk+", Some("3.24.41"))
+        );
+        assert_eq!(split_version("hello"), ("hello", None));
+    }
+
+    #[test]
+    fn test_parse_explicit() {
+        assert_eq!(parse_explicit("apt", "git\nvim\n"), ["git", "vim"]);
+        assert_eq!(
+            parse_explicit("apk", "alpine-base\nvim=9.0.2127-r0\nfd>8\n"),
+            ["alpine-base", "vim", "fd"]
+        );
+        assert_eq!(
+            parse_explicit("xbps", "base-system-0.114_1\nvim-9.0.2116_1\n"),
+            ["base-system", "vim"]
+        );
+        assert_eq!(
+            parse_explicit("dnf", "vim-enhanced\nvim-enhanced\n"),
+            ["vim-enhanced"]
+        );
+    }
+
+    #[test]
+    fn test_parse_essential() {
+        assert_eq!(
+            parse_essential(
+                "apt",
+                "bash yes required\nhtop no optional\nless  important\nwget  standard\n"
+            ),
+            ["bash", "less"]
+        );
+        assert_eq!(
+            parse_essential("dnf", "# dnf itself\ndnf\n\nsystemd-udev\n"),
+            ["dnf", "systemd-udev"]
+        );
+    }
+}
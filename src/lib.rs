@@ -1,12 +1,73 @@
+//! Universal package management, one command line for every os.
+//!
+//! Besides the `upt` binary, the crate plans the commands a package management tool
+//! runs for a task, without running them:
+//!
+//! ```
+//! use upt::{init_vendor, PackageSpec, Task};
+//!
+//! let dnf = init_vendor("dnf").unwrap();
+//! let task = Task::Remove {
+//!     pkgs: vec![PackageSpec::new("vim")],
+//!     confirm: false,
+//! };
+//! assert_eq!(dnf.eval(&task).unwrap().to_string(), "dnf remove vim");
+//! ```
+//!
+//! The documented items follow semver: breaking changes only happen in major versions.
+//! Enums and structs are `#[non_exhaustive]`, new variants and fields may come in minor
+//! versions. Items hidden from the docs serve the `upt` binary and may change at any time.
+
+#![warn(missing_docs)]
+
 #[macro_use]
 mod macros;
 
 mod action;
+mod cache;
+mod completions;
+mod container;
+mod doctor;
 mod error;
+mod exit;
+mod format;
+mod info;
+mod installed;
+mod man;
+mod manifest;
+mod plan;
+mod runner;
+mod search;
+mod signal;
+mod ssh;
 mod task;
 mod utils;
 mod vendor;
 
+#[doc(hidden)]
+pub use cache::Detection;
+#[doc(hidden)]
+pub use completions::{Completion, Shell};
+pub use container::ContainerRunner;
+#[doc(hidden)]
+pub use doctor::doctor;
 pub use error::UptError;
+pub use exit::ExitCode;
+pub use format::Format;
+pub use info::{render_info, PackageInfo};
+pub use installed::{render_installed, InstalledPackage};
+#[doc(hidden)]
+pub use man::man_page;
+pub use manifest::{Manifest, PackageChange};
+pub use plan::{OnFailure, Plan, Step};
+pub use runner::{Capture, CommandRunner, LocalRunner, RecordedCommand, RecordingRunner};
+pub use search::{render_search, SearchResult};
+#[doc(hidden)]
+pub use signal::{signal_code, wait_child};
+pub use ssh::SshRunner;
+pub use task::{PackageSpec, Task};
 pub use utils::detect_os;
-pub use vendor::{detect_vendor, init_vendor, Vendor};
+pub use vendor::{
+    detect_vendor, init_vendor, list_os, list_tools, persona_vendor, select_vendor,
+    select_vendor_with, support_tools, Vendor,
+};
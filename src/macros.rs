@@ -15,6 +15,7 @@ macro_rules! vendors {
             },
         )+
     ) => {
+        /// The vendor of the named tool, e.g. `apt`
         pub fn init_vendor(name: &str) -> Result<$crate::Vendor, $crate::UptError> {
             use $crate::action::must_from_str;
             match name {
@@ -44,15 +45,15 @@ macro_rules! vendors {
                 $(
                     $name => {
                         let (cmd, _) = $install.split_once(' ')?;
-                        Some(cmd)
+                        cmd.split('/').next()
                     }
                 )+
                 _ => None
             }
         }
 
-        #[allow(unused)]
-        pub(crate) fn support_tools() -> Vec<&'static str> {
+        /// Names of all supported package management tools
+        pub fn support_tools() -> Vec<&'static str> {
             vec![$( $name,)+]
         }
     }
@@ -60,18 +61,15 @@ macro_rules! vendors {
 
 macro_rules! os_vendors {
     ($($os:literal => $($tool:literal),+);+$(;)?) => {
-        pub fn detect_vendor(os: &str) -> std::result::Result<$crate::Vendor, $crate::UptError> {
-            let pairs: Vec<(&str, &str)> = match os {
+        /// Candidate tools of each os in order of preference, `*` matches any other os
+        pub(crate) fn os_table() -> Vec<(&'static str, Vec<&'static str>)> {
+            vec![
                 $(
-                    $os => vec![$($tool),+].into_iter().filter_map(|tool| which_cmd(tool).map(|bin_name| (tool, bin_name))).collect(),
+                    ($os, vec![$($tool),+]),
                 )+
-                "windows/msys2" => vec![("pacman","pacman")],
-                _ => ["apt", "dnf", "pacman"].into_iter().map(|tool| (tool, tool)).collect(),
-            };
-            match $crate::utils::find_tool(&pairs) {
-                Some(tool) => $crate::vendor::init_vendor(&tool),
-                None => Err(UptError::NoDetectVendor),
-            }
+                ("windows/msys2", vec!["pacman"]),
+                ("*", vec!["apt", "dnf", "pacman"]),
+            ]
         }
     };
 }
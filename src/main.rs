@@ -1,7 +1,13 @@
-use std::path::Path;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::{env, process};
-use upt::{detect_os, detect_vendor, init_vendor, UptError, Vendor};
+use std::{env, fs, process};
+use upt::{
+    doctor, init_vendor, list_os, list_tools, man_page, persona_vendor, render_info,
+    render_installed, render_search, select_vendor, select_vendor_with, support_tools, Capture,
+    CommandRunner, Completion, ContainerRunner, Detection, ExitCode, Format, LocalRunner, Manifest,
+    PackageSpec, Plan, Shell, SshRunner, Task, UptError, Vendor,
+};
 
 fn main() {
     match run() {
@@ -9,8 +15,19 @@ fn main() {
             process::exit(c);
         }
         Err(e) => {
+            if let Some(UptError::DisplayHelp(t)) = e.downcast_ref::<UptError>() {
+                println!("{t}");
+                process::exit(0);
+            }
             eprintln!("Error: {}", e);
-            process::exit(1);
+            let code = if let Some(e) = e.downcast_ref::<UptError>() {
+                e.exit_code()
+            } else if let Some(e) = e.downcast_ref::<io::Error>() {
+                ExitCode::from(e)
+            } else {
+                ExitCode::Failure
+            };
+            process::exit(code.code());
         }
     }
 }
@@ -22,11 +39,79 @@ fn run() -> Result<i32, Box<dyn std::error::Error>> {
         .unwrap()
         .to_str()
         .unwrap();
-    let vendor = init_vendor(bin)?;
+    let vendor = persona_vendor(bin)?;
     let mut args = vec![bin.to_string()];
     args.extend(env_args.iter().skip(1).cloned());
-    let os = detect_os().unwrap_or_default();
-    let cmd_args = match create_cmd(&vendor, &args, &os) {
+    if bin == "upt" {
+        match args.get(1).map(|v| v.as_str()) {
+            Some("translate") => return translate(&args[2..]),
+            Some("completions") => return completions(&args[2..]),
+            Some("doctor") => {
+                println!("{}", doctor(env::var("UPT_TOOL").ok().as_deref()));
+                return Ok(0);
+            }
+            Some("--man") => {
+                let mut options = args[2..].to_vec();
+                let tool = take_option(&mut options, "--as").unwrap_or_else(|| "upt".to_string());
+                print!("{}", man_page(&persona_vendor(&tool)?));
+                return Ok(0);
+            }
+            Some("--list-tools") | Some("--list-os") => {
+                let mut options = args[2..].to_vec();
+                let format = match take_option(&mut options, "--format") {
+                    Some(v) => v.parse()?,
+                    None => Format::Markdown,
+                };
+                if args[1] == "--list-tools" {
+                    println!("{}", list_tools(format));
+                } else {
+                    println!("{}", list_os(format));
+                }
+                return Ok(0);
+            }
+            _ => {}
+        }
+    }
+    let (host, container) = match bin {
+        "upt" => (
+            take_option(&mut args, "--host"),
+            take_option(&mut args, "--container"),
+        ),
+        _ => (None, None),
+    };
+    let mut runner: Box<dyn CommandRunner> = match (&host, &container) {
+        (Some(_), Some(_)) => {
+            return Err(UptError::InvalidArgs(
+                "--host and --container cannot be used together.".to_string(),
+            )
+            .into())
+        }
+        (Some(v), None) => Box::new(SshRunner::new(v)),
+        (None, Some(v)) => Box::new(v.parse::<ContainerRunner>()?),
+        (None, None) => Box::new(LocalRunner),
+    };
+    // Commands run on another machine or in a container
+    let remote = host.is_some() || container.is_some();
+    // The manifest commands take options of their own
+    match (bin, args.get(1).map(|v| v.as_str())) {
+        ("upt", Some("apply")) => return apply(&args[2..], runner.as_mut(), remote),
+        ("upt", Some("export")) => return export(&args[2..], runner.as_mut(), remote),
+        _ => {}
+    }
+    // `--plan [--format json]` prints what upt would do, the same as `UPT_DRY_RUN=true|json`
+    let mut plan_format = env_plan_format();
+    let mut format: Option<Format> = match bin {
+        "upt" => take_option(&mut args, "--format")
+            .map(|v| v.parse())
+            .transpose()?,
+        _ => None,
+    };
+    if bin == "upt" && take_flag(&mut args, "--plan") {
+        plan_format = Some(format.take());
+    }
+    let refresh = bin == "upt" && take_flag(&mut args, "--refresh");
+    let dry_run = plan_format.is_some();
+    let mut plan = match create_plan(&vendor, &args, runner.as_ref(), remote, dry_run, refresh) {
         Ok(v) => v,
         Err(UptError::DisplayHelp(t)) => {
             println!("{t}");
@@ -34,28 +119,749 @@ fn run() -> Result<i32, Box<dyn std::error::Error>> {
         }
         Err(e) => return Err(e.into()),
     };
-    if let Ok(v) = std::env::var("UPT_DRY_RUN") {
-        if v == "true" || v == "1" {
-            println!("{}", cmd_args.join(" "));
+    match plan_format {
+        Some(None) => {
+            println!("{plan}");
             return Ok(0);
         }
+        Some(Some(Format::Json)) => {
+            println!("{}", plan.to_json());
+            return Ok(0);
+        }
+        Some(Some(_)) => {
+            return Err(
+                UptError::InvalidArgs("The plan is only rendered as json.".to_string()).into(),
+            )
+        }
+        None => {}
     }
-    let cmd = &cmd_args[0];
-    let cmd = match which::which(cmd) {
-        Ok(v) => v,
-        Err(_) => return Err(format!("Command '{cmd}' not found.").into()),
+    let tool = init_vendor(&plan.tool)?;
+    if format.is_some() {
+        plan.structured(&tool);
+    }
+    let last_action = plan.steps.last().map(|v| v.action);
+    if format.is_some() && !matches!(last_action, Some("list_installed" | "search" | "info")) {
+        return Err(UptError::InvalidArgs(
+            "--format only applies to list, search and info.".to_string(),
+        )
+        .into());
+    }
+    // Run interactively, a single command whose exit codes need no translation replaces upt
+    #[cfg(unix)]
+    if let [step] = plan.steps.as_slice() {
+        if !remote
+            && format.is_none()
+            && io::stdin().is_terminal()
+            && io::stdout().is_terminal()
+            && !tool.translates_exit_codes(step.action)
+        {
+            use std::os::unix::process::CommandExt;
+            let bin_path = match &step.path {
+                Some(path) => path.clone(),
+                None => tool.locate(&step.argv[0])?,
+            };
+            let err = Command::new(bin_path).args(&step.argv[1..]).exec();
+            return Err(err.into());
+        }
+    }
+    // A search or info is captured to tell if it found anything
+    let searched = matches!(last_action, Some("search" | "info"));
+    let (code, captured) = plan.run(&tool, runner.as_mut(), format.is_some() || searched)?;
+    let output = String::from_utf8_lossy(&captured);
+    match (format, last_action) {
+        (None, _) => io::stdout().write_all(&captured)?,
+        (Some(format), Some("search")) => {
+            println!("{}", render_search(&tool.parse_search(&output), format))
+        }
+        (Some(format), Some("info")) => {
+            println!("{}", render_info(&tool.parse_info(&output), format))
+        }
+        (Some(format), _) => println!(
+            "{}",
+            render_installed(&tool.parse_installed(&output), format)
+        ),
+    }
+    Ok(code)
+}
+
+fn create_plan(
+    vendor: &Vendor,
+    args: &[String],
+    runner: &dyn CommandRunner,
+    remote: bool,
+    dry_run: bool,
+    refresh: bool,
+) -> Result<Plan, UptError> {
+    let (os, tool, bin_path) = detect(runner, remote, dry_run)?;
+    let task = vendor.parse(args, tool.name())?;
+    let mut plan = Plan::new(&os, &tool, task)?;
+    if refresh {
+        plan.refresh(&tool)?;
+    }
+    // All binaries are located before running any step
+    if !dry_run {
+        plan.locate(&tool, runner, bin_path.as_deref())?;
+    }
+    Ok(plan)
+}
+
+/// The plan format `UPT_DRY_RUN` asks for: Some(None) for text, None if upt really runs
+fn env_plan_format() -> Option<Option<Format>> {
+    match env::var("UPT_DRY_RUN").as_deref() {
+        Ok("true") | Ok("1") => Some(None),
+        Ok("json") => Some(Some(Format::Json)),
+        _ => None,
+    }
+}
+
+/// The os and the tool where the runner runs commands, with the path of the tool's binary if known
+fn detect(
+    runner: &dyn CommandRunner,
+    remote: bool,
+    dry_run: bool,
+) -> Result<(String, Vendor, Option<PathBuf>), UptError> {
+    let detected = match env::var("UPT_TOOL") {
+        Ok(v) if !v.trim().is_empty() => {
+            let os = runner.detect_os().unwrap_or_default();
+            let selected = if remote {
+                select_vendor_with(runner, &v)
+            } else {
+                select_vendor(&v)
+            };
+            match selected {
+                Ok(tool) => (os, tool, None),
+                // Nothing is executed, so the plan of the preferred tool can still be printed
+                Err(UptError::NoAvailableVendor(tools)) if dry_run => {
+                    match tools.iter().find_map(|v| init_vendor(v).ok()) {
+                        Some(tool) => (os, tool, None),
+                        None => return Err(UptError::NoAvailableVendor(tools)),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        _ => {
+            // The cache only holds the detection of this machine
+            let detection = if remote {
+                Detection::detect_with(runner)?
+            } else {
+                Detection::detect()?
+            };
+            (
+                detection.os,
+                init_vendor(&detection.tool)?,
+                Some(detection.bin),
+            )
+        }
+    };
+    Ok(detected)
+}
+
+fn apply(
+    args: &[String],
+    runner: &mut dyn CommandRunner,
+    remote: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let usage = "Usage: upt apply [-f <file>] [--prune] [-y] [--refresh] [--plan]";
+    let mut args = args.to_vec();
+    let plan_format = env_plan_format();
+    if take_option(&mut args, "--format").is_some() || matches!(plan_format, Some(Some(_))) {
+        return Err(UptError::InvalidArgs("--format does not apply to apply.".to_string()).into());
+    }
+    let dry_run = take_flag(&mut args, "--plan") || plan_format.is_some();
+    let refresh = take_flag(&mut args, "--refresh");
+    let file = take_option(&mut args, "-f")
+        .or_else(|| take_option(&mut args, "--file"))
+        .unwrap_or_else(|| "upt.toml".to_string());
+    let confirm = take_flag(&mut args, "-y") | take_flag(&mut args, "--yes");
+    let prune = take_flag(&mut args, "--prune");
+    if !args.is_empty() {
+        return Err(UptError::InvalidArgs(usage.to_string()).into());
+    }
+    let manifest: Manifest = fs::read_to_string(&file)
+        .map_err(|e| UptError::InvalidManifest(format!("cannot read '{file}': {e}")))?
+        .parse()?;
+    // The installed packages are listed even for a dry run, so the tool must be available
+    let (os, tool, bin_path) = detect(runner, remote, false)?;
+    let options = ApplyOptions {
+        prune,
+        confirm,
+        refresh,
+        dry_run,
     };
-    let status = Command::new(cmd).args(&cmd_args[1..]).status()?;
+    apply_manifest(
+        &manifest,
+        &os,
+        &tool,
+        bin_path.as_deref(),
+        runner,
+        &options,
+        &mut io::stdout(),
+    )
+}
 
-    Ok(status.code().unwrap_or_default())
+/// The options of `upt apply`
+#[derive(Debug, Default)]
+struct ApplyOptions {
+    prune: bool,
+    confirm: bool,
+    refresh: bool,
+    dry_run: bool,
 }
 
-fn create_cmd(vendor: &Vendor, args: &[String], os: &str) -> Result<Vec<String>, UptError> {
-    let tool = match std::env::var("UPT_TOOL") {
-        Ok(v) => init_vendor(&v)?,
-        Err(_) => detect_vendor(os)?,
+/// Install the missing packages of the manifest and prune the others if asked, the changes are printed to out
+fn apply_manifest(
+    manifest: &Manifest,
+    os: &str,
+    tool: &Vendor,
+    bin_path: Option<&Path>,
+    runner: &mut dyn CommandRunner,
+    options: &ApplyOptions,
+    out: &mut dyn Write,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let ApplyOptions {
+        prune,
+        confirm,
+        refresh,
+        dry_run,
+    } = *options;
+    let query = if prune {
+        Some(explicit_query(tool, "--prune")?)
+    } else {
+        None
     };
-    let task = vendor.parse(args, tool.name())?;
-    let cmd = tool.eval(&task)?;
-    Ok(cmd)
+    let mut list = Plan::new(os, tool, Task::ListInstalled)?;
+    list.locate(tool, runner, bin_path)?;
+    let (code, stdout) = list.run(tool, runner, true)?;
+    if code != 0 {
+        return Ok(code);
+    }
+    let installed = tool.parse_installed(&String::from_utf8_lossy(&stdout));
+    let changes = manifest.diff(os, tool.name(), &installed)?;
+    let removals = match query {
+        Some(argv) => {
+            let explicit = match run_query(tool, runner, &argv)? {
+                Some(output) => tool.parse_explicit(&output),
+                None => return Ok(ExitCode::Failure.code()),
+            };
+            // Without the packages the os needs nothing could be pruned safely
+            let essential = match tool.essential_query() {
+                Some(argv) => match run_query(tool, runner, &argv)? {
+                    Some(output) => tool.parse_essential(&output),
+                    None => return Ok(ExitCode::Failure.code()),
+                },
+                None => vec![],
+            };
+            manifest.prune(os, tool.name(), &explicit, &essential)
+        }
+        None => vec![],
+    };
+    if changes.is_empty() && removals.is_empty() {
+        writeln!(
+            out,
+            "Nothing to do, the installed packages match the manifest."
+        )?;
+        return Ok(0);
+    }
+    let mut plans = vec![];
+    if !changes.is_empty() {
+        let pkgs = changes.iter().map(|v| v.spec.clone()).collect();
+        let mut plan = Plan::new(os, tool, Task::Install { pkgs, confirm })?;
+        if refresh {
+            plan.refresh(tool)?;
+        }
+        plans.push(plan);
+    }
+    if !removals.is_empty() {
+        let pkgs = removals.iter().map(|v| PackageSpec::new(v)).collect();
+        plans.push(Plan::new(os, tool, Task::Remove { pkgs, confirm })?);
+    }
+    // Removals are always previewed
+    if dry_run || !removals.is_empty() {
+        for change in &changes {
+            writeln!(out, "{change}")?;
+        }
+        for name in &removals {
+            writeln!(out, "- {name}")?;
+        }
+    }
+    if dry_run {
+        for plan in &plans {
+            writeln!(out, "{plan}")?;
+        }
+        return Ok(0);
+    }
+    if !removals.is_empty() && !confirm && !ask(&format!("Remove {} packages?", removals.len()))? {
+        writeln!(out, "Aborted.")?;
+        return Ok(ExitCode::Failure.code());
+    }
+    for plan in plans.iter_mut() {
+        plan.locate(tool, runner, bin_path)?;
+    }
+    for plan in &plans {
+        let (code, _) = plan.run(tool, runner, false)?;
+        if code != 0 {
+            return Ok(code);
+        }
+    }
+    Ok(0)
+}
+
+/// The tool's query of the explicitly installed packages, the option needs one
+fn explicit_query(tool: &Vendor, option: &str) -> Result<Vec<String>, UptError> {
+    tool.explicit_query().ok_or_else(|| {
+        UptError::InvalidArgs(format!("{option} is not supported with '{}'.", tool.name()))
+    })
+}
+
+/// The stdout of a query of the tool, None if it fails
+fn run_query(
+    tool: &Vendor,
+    runner: &mut dyn CommandRunner,
+    argv: &[String],
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut argv = argv.to_vec();
+    argv[0] = tool
+        .locate_with(runner, &argv[0])?
+        .to_string_lossy()
+        .to_string();
+    let output = runner.run(&argv, &[], None, Capture::Stdout)?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+/// Ask the user in the terminal, fails if upt does not run in one
+fn ask(question: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(UptError::InvalidArgs(
+            "Pass -y to confirm, upt cannot ask outside a terminal.".to_string(),
+        )
+        .into());
+    }
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn export(
+    args: &[String],
+    runner: &mut dyn CommandRunner,
+    remote: bool,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let usage = "Usage: upt export [--explicit|--all] [--format upt|json|txt]";
+    let mut args = args.to_vec();
+    let explicit = take_flag(&mut args, "--explicit");
+    let all = take_flag(&mut args, "--all");
+    let format = take_option(&mut args, "--format").unwrap_or_else(|| "upt".to_string());
+    if !args.is_empty() || (explicit && all) {
+        return Err(UptError::InvalidArgs(usage.to_string()).into());
+    }
+    if !["upt", "json", "txt"].contains(&format.as_str()) {
+        return Err(UptError::InvalidArgs(format!(
+            "Unknown format '{format}', expected upt, json or txt."
+        ))
+        .into());
+    }
+    let (os, tool, bin_path) = detect(runner, remote, false)?;
+    // Only the explicitly installed packages by default, the dependencies follow them
+    let explicit = explicit || (!all && tool.explicit_query().is_some());
+    if !explicit && !all {
+        eprintln!(
+            "Warning: {} cannot tell the explicitly installed packages, all of them are exported.",
+            tool.name()
+        );
+    }
+    export_manifest(
+        &os,
+        &tool,
+        bin_path.as_deref(),
+        runner,
+        explicit,
+        &format,
+        &mut io::stdout(),
+    )
+}
+
+/// Print the installed packages, or only the explicitly installed ones, as a manifest in the format
+fn export_manifest(
+    os: &str,
+    tool: &Vendor,
+    bin_path: Option<&Path>,
+    runner: &mut dyn CommandRunner,
+    explicit: bool,
+    format: &str,
+    out: &mut dyn Write,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let query = if explicit {
+        Some(explicit_query(tool, "--explicit")?)
+    } else {
+        None
+    };
+    let mut list = Plan::new(os, tool, Task::ListInstalled)?;
+    list.locate(tool, runner, bin_path)?;
+    let (code, stdout) = list.run(tool, runner, true)?;
+    if code != 0 {
+        return Ok(code);
+    }
+    let mut names: Vec<String> = tool
+        .parse_installed(&String::from_utf8_lossy(&stdout))
+        .into_iter()
+        .map(|v| v.name)
+        .collect();
+    if let Some(argv) = query {
+        match run_query(tool, runner, &argv)? {
+            Some(output) => {
+                let explicit = tool.parse_explicit(&output);
+                names.retain(|v| explicit.contains(v));
+            }
+            None => return Ok(ExitCode::Failure.code()),
+        }
+    }
+    let manifest = Manifest::export(tool.name(), &names);
+    match format {
+        "json" => writeln!(out, "{}", manifest.to_json())?,
+        "txt" => writeln!(out, "{}", manifest.packages.join("\n"))?,
+        _ => writeln!(out, "{}", manifest.to_toml())?,
+    }
+    Ok(0)
+}
+
+fn translate(args: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    let rows = translations(args)?;
+    if let [(_, cmd)] = rows.as_slice() {
+        println!("{cmd}");
+    } else {
+        let width = rows.iter().map(|(v, _)| v.len()).max().unwrap_or_default();
+        for (name, cmd) in rows {
+            println!("{name:<width$}  {cmd}");
+        }
+    }
+    Ok(0)
+}
+
+/// The command translated for each target tool, `-` if the tool cannot do it
+fn translations(args: &[String]) -> Result<Vec<(String, String)>, UptError> {
+    let usage = "Usage: upt translate --to <tool>/--all <command>...";
+    let mut all = false;
+    let mut to = None;
+    // The options end at the command, whose own options are left alone
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--to" => {
+                i += 1;
+                to = Some(
+                    args.get(i)
+                        .ok_or(UptError::InvalidArgs(usage.to_string()))?
+                        .clone(),
+                );
+            }
+            "-h" | "--help" => return Err(UptError::DisplayHelp(usage.to_string())),
+            v if v.starts_with("--to=") => to = Some(v["--to=".len()..].to_string()),
+            v if v.starts_with('-') => return Err(UptError::InvalidArgs(usage.to_string())),
+            _ => break,
+        }
+        i += 1;
+    }
+    let mut args = args[i..].to_vec();
+    let tools: Vec<String> = match to {
+        Some(v) => v.split(',').map(|v| v.trim().to_string()).collect(),
+        None if all => support_tools()
+            .into_iter()
+            .filter(|v| *v != "upt")
+            .map(|v| v.to_string())
+            .collect(),
+        None => return Err(UptError::InvalidArgs(usage.to_string())),
+    };
+    if args.is_empty() {
+        return Err(UptError::InvalidArgs(usage.to_string()));
+    }
+    // The command is written in upt's syntax unless it starts with a tool's name
+    let vendor = match persona_vendor(&args[0]) {
+        Ok(v) => v,
+        Err(_) => {
+            args.insert(0, "upt".to_string());
+            init_vendor("upt")?
+        }
+    };
+    let mut rows = vec![];
+    for name in tools {
+        let tool = init_vendor(&name)?;
+        let task = vendor.parse(&args, tool.name())?;
+        let cmd = match Plan::new("", &tool, task) {
+            // One row per tool, the steps run one after another
+            Ok(v) => v.to_string().replace('\n', " && "),
+            Err(UptError::InvalidTask) => "-".to_string(),
+            Err(e) => return Err(e),
+        };
+        rows.push((name, cmd));
+    }
+    Ok(rows)
+}
+
+fn completions(args: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    let usage = "Usage: upt completions <bash|zsh|fish|powershell|elvish> [--as <tool>]";
+    let mut args = args.to_vec();
+    let tool = take_option(&mut args, "--as").unwrap_or_else(|| "upt".to_string());
+    let shell: Shell = match args.as_slice() {
+        [shell] => shell.parse()?,
+        _ => return Err(UptError::InvalidArgs(usage.to_string()).into()),
+    };
+    let vendor = persona_vendor(&tool)?;
+    let mut completion = Completion::new(&vendor);
+    if vendor.name() == "upt" {
+        let shells = ["bash", "zsh", "fish", "powershell", "elvish"];
+        let formats = ["--format", "table", "markdown", "json", "csv"];
+        completion.add("upt", "translate", &["--to", "--all"]);
+        completion.add("upt", "completions", &[&shells[..], &["--as"]].concat());
+        completion.add("upt", "doctor", &[]);
+        completion.add(
+            "upt",
+            "apply",
+            &[
+                "-f",
+                "--file",
+                "--prune",
+                "-y",
+                "--yes",
+                "--refresh",
+                "--plan",
+            ],
+        );
+        completion.add(
+            "upt",
+            "export",
+            &["--explicit", "--all", "--format", "upt", "json", "txt"],
+        );
+        completion.add("upt", "install", &["--refresh"]);
+        completion.add("upt", "list", &formats);
+        completion.add("upt", "search", &formats);
+        completion.add("upt", "info", &formats);
+        completion.add("upt", "show", &formats);
+        completion.add("upt", "--man", &["--as"]);
+        completion.add("upt", "--list-tools", &formats);
+        completion.add("upt", "--list-os", &formats);
+    }
+    print!("{}", completion.generate(shell));
+    Ok(0)
+}
+
+/// Remove the flag from args, returns whether it was present
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|v| v == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Remove the option and its value from args, supports `--name value` and `--name=value`
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    let i = args
+        .iter()
+        .position(|v| v == name || v.starts_with(&prefix))?;
+    let arg = args.remove(i);
+    match arg.strip_prefix(&prefix) {
+        Some(v) => Some(v.to_string()),
+        None if i < args.len() => Some(args.remove(i)),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use upt::RecordingRunner;
+
+    const APT_LIST: &str = "Listing...
+git/stable,now 1:2.39.2-1.1 amd64 [installed]
+htop/stable,now 3.2.2-2 amd64 [installed]
+ripgrep/stable,now 13.0.0-4+b2 amd64 [installed]
+bash/stable,now 5.2.15-2+b2 amd64 [installed]
+fd-find/stable,now 8.6.0-3 amd64 [installed,automatic]
+";
+
+    fn commands(runner: &RecordingRunner) -> Vec<String> {
+        runner.commands.iter().map(|v| v.argv.join(" ")).collect()
+    }
+
+    fn apply_with(
+        manifest: &str,
+        runner: &mut RecordingRunner,
+        options: &ApplyOptions,
+    ) -> (i32, String) {
+        let manifest: Manifest = manifest.parse().unwrap();
+        let apt = init_vendor("apt").unwrap();
+        let mut out = vec![];
+        let code =
+            apply_manifest(&manifest, "debian", &apt, None, runner, options, &mut out).unwrap();
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    fn words(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_translations() {
+        let rows = translations(&words("--to apt,dnf install vim")).unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("apt".to_string(), "apt install vim".to_string()),
+                ("dnf".to_string(), "dnf install vim".to_string()),
+            ]
+        );
+        // The options of the translated command are not upt's
+        let rows = translations(&words("--to=apt pacman -S -y -u")).unwrap();
+        assert_eq!(rows[0].1, "apt upgrade");
+        assert!(translations(&words("--to apt pacman -S --all vim")).is_err());
+        let rows = translations(&words("--to nix-env upgrade")).unwrap();
+        assert_eq!(rows[0].1, "nix-channel --update && nix-env -u");
+        assert_eq!(
+            translations(&words("--all search jq")).unwrap().len(),
+            support_tools().len() - 1
+        );
+        assert!(matches!(
+            translations(&words("-h")),
+            Err(UptError::DisplayHelp(_))
+        ));
+        assert!(matches!(
+            translations(&words("install vim")),
+            Err(UptError::InvalidArgs(_))
+        ));
+    }
+
+    #[test]
+    fn test_apply() {
+        let manifest = "packages = [\"git\", \"vim\", \"ripgrep\"]\n[pins]\nripgrep = \"14.1.0\"";
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apt", "list"], 0, APT_LIST);
+        let options = ApplyOptions {
+            confirm: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_with(manifest, &mut runner, &options),
+            (0, String::new())
+        );
+        assert_eq!(
+            commands(&runner),
+            [
+                "/usr/bin/apt list -i",
+                "/usr/bin/apt install vim ripgrep=14.1.0 -y"
+            ]
+        );
+
+        // The changes and the plan are printed, nothing is installed
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apt", "list"], 0, APT_LIST);
+        let options = ApplyOptions {
+            dry_run: true,
+            refresh: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_with(manifest, &mut runner, &options),
+            (
+                0,
+                "+ vim\n~ ripgrep 13.0.0-4+b2 -> 14.1.0\napt update\napt install vim ripgrep=14.1.0\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(commands(&runner), ["/usr/bin/apt list -i"]);
+
+        // A failed listing installs nothing
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apt", "list"], 100, "");
+        assert_eq!(apply_with(manifest, &mut runner, &options).0, 1);
+        assert_eq!(commands(&runner).len(), 1);
+    }
+
+    #[test]
+    fn test_apply_prune() {
+        let manifest = "packages = [\"git\", \"ripgrep\"]";
+        let options = ApplyOptions {
+            prune: true,
+            confirm: true,
+            ..Default::default()
+        };
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apt", "list"], 0, APT_LIST);
+        runner.respond(&["apt-mark", "showmanual"], 0, "bash\ngit\nhtop\nripgrep\n");
+        runner.respond(
+            &["dpkg-query"],
+            0,
+            "bash yes required\ngit  optional\nhtop  optional\n",
+        );
+        assert_eq!(
+            apply_with(manifest, &mut runner, &options),
+            (0, "- htop\n".to_string())
+        );
+        assert_eq!(
+            commands(&runner),
+            [
+                "/usr/bin/apt list -i",
+                "/usr/bin/apt-mark showmanual",
+                "/usr/bin/dpkg-query -W -f ${Package} ${Essential} ${Priority}\n",
+                "/usr/bin/apt remove htop -y",
+            ]
+        );
+
+        // Nothing is pruned without the packages the os needs
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apt", "list"], 0, APT_LIST);
+        runner.respond(&["apt-mark", "showmanual"], 0, "bash\ngit\nhtop\nripgrep\n");
+        runner.respond(&["dpkg-query"], 2, "");
+        assert_eq!(
+            apply_with(manifest, &mut runner, &options),
+            (1, String::new())
+        );
+        assert_eq!(commands(&runner).len(), 3);
+    }
+
+    #[test]
+    fn test_export() {
+        let apt = init_vendor("apt").unwrap();
+        let export = |explicit: bool, format: &str| {
+            let mut runner = RecordingRunner::new();
+            runner.respond(&["apt", "list"], 0, APT_LIST);
+            runner.respond(&["apt-mark", "showmanual"], 0, "git\nfd-find\nhtop\n");
+            let mut out = vec![];
+            let code = export_manifest(
+                "debian",
+                &apt,
+                None,
+                &mut runner,
+                explicit,
+                format,
+                &mut out,
+            )
+            .unwrap();
+            (code, String::from_utf8(out).unwrap(), commands(&runner))
+        };
+        let (code, out, commands) = export(false, "txt");
+        assert_eq!(code, 0);
+        // In distro-neutral names
+        assert_eq!(out, "git\nhtop\nripgrep\nbash\nfd\n");
+        assert_eq!(commands, ["/usr/bin/apt list -i"]);
+
+        let (_, out, commands) = export(true, "upt");
+        assert_eq!(
+            out,
+            "packages = [\n    \"git\",\n    \"htop\",\n    \"fd\",\n]\n"
+        );
+        assert_eq!(
+            commands,
+            ["/usr/bin/apt list -i", "/usr/bin/apt-mark showmanual"]
+        );
+    }
 }
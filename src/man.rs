@@ -0,0 +1,116 @@
+use crate::vendor::{os_groups, Vendor};
+
+/// Render the roff man page of upt or a tool that upt acts as
+pub fn man_page(vendor: &Vendor) -> String {
+    let name = vendor.name();
+    let mut lines = vec![
+        format!(
+            ".TH {} 1 \"\" \"upt {}\" \"User Commands\"",
+            escape(&name.to_uppercase()),
+            env!("CARGO_PKG_VERSION")
+        ),
+        ".SH NAME".to_string(),
+    ];
+    if name == "upt" {
+        lines.push("upt \\- universal package management tool for any os".to_string());
+    } else {
+        lines.push(format!(
+            "{} \\- manage packages with the syntax of {}, performed by upt",
+            escape(name),
+            escape(name)
+        ));
+    }
+    lines.push(".SH SYNOPSIS".to_string());
+    for (usage, _) in vendor.usages() {
+        lines.push(".br".to_string());
+        lines.push(escape(&usage));
+    }
+    lines.push(".SH DESCRIPTION".to_string());
+    if name == "upt" {
+        lines.push(
+            "Upt provides a unified command interface to manage packages for any operating system. \
+            It identifies the os, picks the package management tool of the os and runs it to perform the task."
+                .to_string(),
+        );
+    } else {
+        lines.push(format!(
+            "This is upt acting as {}. \
+            The command line is parsed with the syntax of {}, \
+            the task is then performed by the package management tool of the os.",
+            escape(name),
+            escape(name)
+        ));
+    }
+    lines.push(".SH COMMANDS".to_string());
+    for (usage, description) in vendor.usages() {
+        lines.push(".TP".to_string());
+        lines.push(format!(".B {}", escape(&usage)));
+        lines.push(escape(description));
+    }
+    lines.push(".SH OPTIONS".to_string());
+    if !vendor.confirm.trim().is_empty() {
+        lines.push(".TP".to_string());
+        lines.push(format!(".B {}", escape(vendor.confirm.trim())));
+        lines.push("Perform the task without asking for confirmation.".to_string());
+    }
+    lines.push(".TP".to_string());
+    lines.push(format!(".B {}", escape("-h/--help")));
+    lines.push("Print help.".to_string());
+    lines.push(".SH ENVIRONMENT".to_string());
+    lines.push(".TP".to_string());
+    lines.push(".B UPT_TOOL".to_string());
+    lines.push(
+        "The package management tool to use instead of the detected one. \
+        Accepts a comma-separated list in order of preference, the first installed tool is used."
+            .to_string(),
+    );
+    lines.push(".TP".to_string());
+    lines.push(".B UPT_DRY_RUN".to_string());
+    lines.push("Set to true or 1 to print the command instead of running it, set to json to print the plan as json.".to_string());
+    lines.push(".SH FILES".to_string());
+    lines.push(".TP".to_string());
+    lines.push(".I $XDG_CACHE_HOME/upt/detect".to_string());
+    lines.push("Cache of the detected os and tool.".to_string());
+    lines.push(".SH OS MAPPING".to_string());
+    lines.push("The tool is picked by the os id from /etc/os-release, in order:".to_string());
+    for (oses, tools) in os_groups() {
+        lines.push(".TP".to_string());
+        lines.push(format!(".B {}", escape(&oses.join(", "))));
+        lines.push(escape(&tools.join(", ")));
+    }
+    lines.push(".SH SEE ALSO".to_string());
+    lines.push(env!("CARGO_PKG_HOMEPAGE").to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Escape text for roff
+fn escape(v: &str) -> String {
+    let v = v.replace('\\', "\\e").replace('-', "\\-");
+    if v.starts_with(['.', '\'']) {
+        format!("\\&{v}")
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vendor::init_vendor;
+
+    #[test]
+    fn test_man_page() {
+        let page = man_page(&init_vendor("apt").unwrap());
+        assert!(page.starts_with(".TH APT 1 "));
+        assert!(page.contains("\n.B apt/apt\\-get install <pkg>\nInstall packages\n"));
+        assert!(page.contains("\n.B \\-y/\\-\\-yes/\\-\\-assume\\-yes\n"));
+        assert!(page.contains("\n.B ubuntu, debian, "));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("-y/--yes"), "\\-y/\\-\\-yes");
+        assert_eq!(escape(".hidden"), "\\&.hidden");
+        assert_eq!(escape("a\\b"), "a\\eb");
+    }
+}
@@ -0,0 +1,906 @@
+use crate::error::UptError;
+use crate::format::{json_array, json_str};
+use crate::installed::InstalledPackage;
+use crate::task::PackageSpec;
+use crate::vendor::support_tools;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The packages a machine should have, read from `upt.toml`.
+///
+/// ```toml
+/// packages = ["git", "ripgrep", "fd"]
+///
+/// [pins]
+/// ripgrep = "14.1.0"
+///
+/// [os.debian]
+/// packages = ["build-essential"]
+///
+/// [vendor.apt.rename]
+/// fd = "fd-find"
+/// ```
+///
+/// `[os.<id>]` and `[vendor.<tool>]` tables add `packages` and drop the ones listed in `exclude`,
+/// their `rename` tables name the packages that differ on the os or for the tool, the tool's wins.
+/// Pins are keyed by the names of the manifest.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Manifest {
+    /// Packages wanted on every os, from `packages = [...]`
+    pub packages: Vec<String>,
+    /// Versions the packages are pinned to, from the `[pins]` table
+    pub pins: Vec<(String, String)>,
+    /// Packages never pruned besides the system's own, e.g. the kernel, from `protect = [...]`
+    pub protect: Vec<String>,
+    overrides: Vec<Override>,
+}
+
+/// Packages never pruned, removing them breaks the system or the tool itself
+pub(crate) const PROTECTED: &[&str] = &[
+    "alpine-base",
+    "apk-tools",
+    "apt",
+    "base",
+    "base-system",
+    "bash",
+    "brew",
+    "ca-certificates",
+    "coreutils",
+    "dnf",
+    "dpkg",
+    "glibc",
+    "openssh",
+    "openssh-server",
+    "pacman",
+    "pkg",
+    "rpm",
+    "sudo",
+    "upt",
+    "xbps",
+];
+
+/// Families of packages never pruned, e.g. `linux-image-6.1.0-18-amd64` or `kernel-core`
+const PROTECTED_PREFIXES: [&str; 13] = [
+    "base-files",
+    "e2fsprogs",
+    "grub",
+    "init",
+    "initramfs",
+    "kernel",
+    "libc",
+    "linux",
+    "shim",
+    "systemd",
+    "sysvinit",
+    "udev",
+    "util-linux",
+];
+
+/// Whether the package is never pruned: one of [`PROTECTED`] or of a protected family.
+///
+/// A family is a prefix followed by `-` or a digit, so `grub2-efi-x64` is protected but `initdb` is not.
+pub(crate) fn protected(name: &str) -> bool {
+    PROTECTED.contains(&name)
+        || PROTECTED_PREFIXES.iter().any(|prefix| {
+            name.strip_prefix(prefix).is_some_and(|rest| {
+                rest.is_empty() || rest.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+            })
+        })
+}
+
+/// An `[os.<id>]` or `[vendor.<tool>]` table
+#[derive(Debug, Clone, PartialEq)]
+struct Override {
+    /// `os` or `vendor`
+    scope: String,
+    name: String,
+    packages: Vec<String>,
+    exclude: Vec<String>,
+    renames: Vec<(String, String)>,
+}
+
+/// Packages named differently by some tools: the distro-neutral name of the manifest,
+/// the tool and its name. Tools sharing a repository are listed under one, see [`family`].
+const NAMES: [(&str, &str, &str); 20] = [
+    ("docker", "apt", "docker.io"),
+    ("fd", "apt", "fd-find"),
+    ("fd", "dnf", "fd-find"),
+    ("go", "apt", "golang-go"),
+    ("go", "dnf", "golang"),
+    ("netcat", "apt", "netcat-openbsd"),
+    ("netcat", "pacman", "openbsd-netcat"),
+    ("nodejs", "brew", "node"),
+    ("openssh", "apt", "openssh-client"),
+    ("openssh", "dnf", "openssh-clients"),
+    ("pip", "apk", "py3-pip"),
+    ("pip", "apt", "python3-pip"),
+    ("pip", "dnf", "python3-pip"),
+    ("pip", "pacman", "python-pip"),
+    ("python3", "brew", "python"),
+    ("python3", "pacman", "python"),
+    ("rust", "apt", "rustc"),
+    ("the_silver_searcher", "apt", "silversearcher-ag"),
+    ("vim", "dnf", "vim-enhanced"),
+    ("xz", "apt", "xz-utils"),
+];
+
+/// The tool whose package names the tool shares
+fn family(tool: &str) -> &str {
+    match tool {
+        "nala" | "pkg(termux)" => "apt",
+        "yum" => "dnf",
+        "yay" | "paru" => "pacman",
+        _ => tool,
+    }
+}
+
+/// The tool's name of a package of the manifest
+fn native_name<'a>(name: &'a str, tool: &str) -> &'a str {
+    NAMES
+        .iter()
+        .find(|(v, t, _)| *v == name && *t == family(tool))
+        .map(|(_, _, native)| *native)
+        .unwrap_or(name)
+}
+
+/// The name of a package of the tool without its category, emerge lists `app-editors/vim`
+fn short_name<'a>(name: &'a str, tool: &str) -> &'a str {
+    match name.split_once('/') {
+        Some((_, short)) if tool == "emerge" => short,
+        _ => name,
+    }
+}
+
+/// The distro-neutral name of a package of the tool
+fn neutral_name<'a>(name: &'a str, tool: &str) -> &'a str {
+    let name = short_name(name, tool);
+    NAMES
+        .iter()
+        .find(|(_, t, native)| *native == name && *t == family(tool))
+        .map(|(v, _, _)| *v)
+        .unwrap_or(name)
+}
+
+/// A package of the manifest that is not installed as wanted
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct PackageChange {
+    /// The package as the tool names it, at its pinned version if any
+    pub spec: PackageSpec,
+    /// The installed version if another version of the package is installed
+    pub installed: Option<String>,
+}
+
+/// `+ name` for a missing package, `~ name installed -> pinned` for another version
+impl fmt::Display for PackageChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.installed, &self.spec.version) {
+            (Some(installed), Some(version)) => {
+                write!(f, "~ {} {} -> {}", self.spec.name, installed, version)
+            }
+            _ => write!(f, "+ {}", self.spec),
+        }
+    }
+}
+
+impl Manifest {
+    /// A manifest of the packages of the tool, in distro-neutral names
+    pub fn export(tool: &str, names: &[String]) -> Self {
+        let mut packages: Vec<String> = vec![];
+        for name in names {
+            let name = neutral_name(name, tool).to_string();
+            if !packages.contains(&name) {
+                packages.push(name);
+            }
+        }
+        Manifest {
+            packages,
+            ..Default::default()
+        }
+    }
+
+    /// Render the packages and pins as `upt.toml`, the overrides are not rendered
+    pub fn to_toml(&self) -> String {
+        let mut lines = vec!["packages = [".to_string()];
+        lines.extend(
+            self.packages
+                .iter()
+                .map(|v| format!("    {},", json_str(v))),
+        );
+        lines.push("]".to_string());
+        if !self.protect.is_empty() {
+            lines.push(format!("protect = {}", json_array(&self.protect)));
+        }
+        if !self.pins.is_empty() {
+            lines.push(String::new());
+            lines.push("[pins]".to_string());
+            lines.extend(
+                self.pins
+                    .iter()
+                    .map(|(k, v)| format!("{} = {}", json_str(k), json_str(v))),
+            );
+        }
+        lines.join("\n")
+    }
+
+    /// Render the packages as a json object
+    pub fn to_json(&self) -> String {
+        format!("{{\"packages\": {}}}", json_array(&self.packages))
+    }
+
+    /// The packages wanted on the os with the tool, in the tool's names and pinned.
+    ///
+    /// Names without a rename in the manifest are translated with a builtin table, e.g. `fd` is `fd-find` for apt.
+    pub fn resolve(&self, os: &str, tool: &str) -> Vec<PackageSpec> {
+        let scopes: Vec<&Override> = [("os", os), ("vendor", tool)]
+            .iter()
+            .filter_map(|(scope, name)| {
+                self.overrides
+                    .iter()
+                    .find(|v| v.scope == *scope && v.name == *name)
+            })
+            .collect();
+        let mut names = self.packages.clone();
+        for scope in &scopes {
+            for name in &scope.packages {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            names.retain(|v| !scope.exclude.contains(v));
+        }
+        names
+            .iter()
+            .map(|name| {
+                let native = scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| lookup(&scope.renames, name))
+                    .unwrap_or_else(|| native_name(name, tool));
+                PackageSpec {
+                    name: native.to_string(),
+                    version: lookup(&self.pins, name).map(|v| v.to_string()),
+                }
+            })
+            .collect()
+    }
+
+    /// Explicitly installed packages the manifest does not list.
+    ///
+    /// The packages the system needs, e.g. the kernel, the ones of `protect` and the `essential` ones of the os are kept,
+    /// see [`Vendor::essential_query`](crate::Vendor::essential_query).
+    pub fn prune(
+        &self,
+        os: &str,
+        tool: &str,
+        explicit: &[String],
+        essential: &[String],
+    ) -> Vec<String> {
+        let wanted = self.resolve(os, tool);
+        explicit
+            .iter()
+            .filter(|name| {
+                let short = short_name(name, tool).to_string();
+                !wanted.iter().any(|v| v.name == **name || v.name == short)
+                    && !protected(&short)
+                    && !self.protect.contains(name)
+                    && !self.protect.contains(&short)
+                    && !essential.contains(name)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Packages wanted on the os with the tool that are missing or installed at another version.
+    ///
+    /// Fails if a pinned package is installed but the tool does not list its version.
+    pub fn diff(
+        &self,
+        os: &str,
+        tool: &str,
+        installed: &[InstalledPackage],
+    ) -> Result<Vec<PackageChange>, UptError> {
+        let mut changes = vec![];
+        for spec in self.resolve(os, tool) {
+            let package = installed
+                .iter()
+                .find(|v| v.name == spec.name || short_name(&v.name, tool) == spec.name);
+            match (package, &spec.version) {
+                (None, _) => changes.push(PackageChange {
+                    spec,
+                    installed: None,
+                }),
+                (Some(package), Some(version)) => match &package.version {
+                    Some(v) if !version_matches(v, version) => changes.push(PackageChange {
+                        installed: Some(v.clone()),
+                        spec,
+                    }),
+                    Some(_) => {}
+                    None => {
+                        return Err(UptError::InvalidManifest(format!(
+                        "cannot check the pin of '{}', {tool} does not list its installed version",
+                        spec.name
+                    )))
+                    }
+                },
+                (Some(_), None) => {}
+            }
+        }
+        Ok(changes)
+    }
+}
+
+impl Manifest {
+    /// The `[os.<id>]` or `[vendor.<tool>]` table, added if it is not there yet
+    fn scope(&mut self, scope: &str, name: &str) -> Result<&mut Override, String> {
+        if scope == "vendor" && !support_tools().contains(&name) {
+            return Err(format!("unknown tool '{name}'"));
+        }
+        let i = match self
+            .overrides
+            .iter()
+            .position(|v| v.scope == scope && v.name == name)
+        {
+            Some(i) => i,
+            None => {
+                self.overrides.push(Override {
+                    scope: scope.to_string(),
+                    name: name.to_string(),
+                    packages: vec![],
+                    exclude: vec![],
+                    renames: vec![],
+                });
+                self.overrides.len() - 1
+            }
+        };
+        Ok(&mut self.overrides[i])
+    }
+}
+
+fn lookup<'a>(pairs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Whether the installed version is the pinned one, `2:9.0.1378-2` matches `9.0.1378`
+fn version_matches(installed: &str, pinned: &str) -> bool {
+    let installed = match installed.split_once(':') {
+        Some((epoch, v)) if epoch.chars().all(|c| c.is_ascii_digit()) => v,
+        _ => installed,
+    };
+    match installed.strip_prefix(pinned) {
+        Some(rest) => !rest.starts_with(|c: char| c.is_ascii_alphanumeric()),
+        None => false,
+    }
+}
+
+/// A value of the TOML subset the manifest is written in
+#[derive(Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Array(Vec<String>),
+}
+
+impl FromStr for Manifest {
+    type Err = UptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut manifest = Manifest::default();
+        for (line, table, key, value) in parse_toml(s)? {
+            let err = |v: &str| UptError::InvalidManifest(format!("line {line}: {v}"));
+            match (table.as_slice(), value) {
+                ([], Value::Array(v)) if key == "packages" => manifest.packages = v,
+                ([], Value::Array(v)) if key == "protect" => manifest.protect = v,
+                ([], Value::Str(_)) if key == "packages" || key == "protect" => {
+                    return Err(err("expected an array"))
+                }
+                ([t], Value::Str(v)) if t == "pins" => manifest.pins.push((key, v)),
+                ([scope, name], value) if scope == "os" || scope == "vendor" => {
+                    let scope = manifest.scope(scope, name).map_err(|v| err(&v))?;
+                    match value {
+                        Value::Array(v) if key == "packages" => scope.packages = v,
+                        Value::Array(v) if key == "exclude" => scope.exclude = v,
+                        Value::Str(_) if key == "packages" || key == "exclude" => {
+                            return Err(err("expected an array"))
+                        }
+                        _ => return Err(err(&format!("unknown key '{key}'"))),
+                    }
+                }
+                ([scope, name, t], value)
+                    if (scope == "os" || scope == "vendor") && t == "rename" =>
+                {
+                    let scope = manifest.scope(scope, name).map_err(|v| err(&v))?;
+                    match value {
+                        Value::Str(v) => scope.renames.push((key, v)),
+                        Value::Array(_) => return Err(err("expected a string")),
+                    }
+                }
+                _ => {
+                    let key = table.iter().chain([&key]).cloned().collect::<Vec<_>>();
+                    return Err(err(&format!("unexpected key '{}'", key.join("."))));
+                }
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// A key of the TOML document: its line, table, name and value
+type Entry = (usize, Vec<String>, String, Value);
+
+/// Parse the TOML subset: tables, string keys, strings and arrays of strings
+fn parse_toml(s: &str) -> Result<Vec<Entry>, UptError> {
+    let mut entries = vec![];
+    let mut table = vec![];
+    let mut lines = s.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        let line_no = i + 1;
+        let err_at = |n: usize, v: &str| UptError::InvalidManifest(format!("line {n}: {v}"));
+        let err = |v: &str| err_at(line_no, v);
+        let mut rest = line.trim();
+        if rest.is_empty() || rest.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = rest.strip_prefix('[') {
+            let (keys, rest) = parse_keys(header).map_err(|v| err(&v))?;
+            let rest = rest.strip_prefix(']').ok_or_else(|| err("expected `]`"))?;
+            end_of_line(rest).map_err(|v| err(&v))?;
+            table = keys;
+            continue;
+        }
+        let (keys, after) = parse_keys(rest).map_err(|v| err(&v))?;
+        let [key] = keys.as_slice() else {
+            return Err(err("dotted keys are not supported"));
+        };
+        rest = after
+            .strip_prefix('=')
+            .ok_or_else(|| err("expected `=`"))?
+            .trim_start();
+        let value = if let Some(mut items) = rest.strip_prefix('[') {
+            // Arrays may span several lines
+            let mut values = vec![];
+            let mut item_line = line_no;
+            let mut buf;
+            loop {
+                items = items.trim_start();
+                if let Some(after) = items.strip_prefix(']') {
+                    end_of_line(after).map_err(|v| err_at(item_line, &v))?;
+                    break;
+                }
+                if items.is_empty() || items.starts_with('#') {
+                    match lines.next() {
+                        Some((i, next)) => {
+                            item_line = i + 1;
+                            buf = next.to_string();
+                            items = &buf;
+                            continue;
+                        }
+                        None => return Err(err("unclosed array")),
+                    }
+                }
+                let (value, after) = parse_str(items).map_err(|v| err_at(item_line, &v))?;
+                values.push(value);
+                items = after.trim_start();
+                items = items.strip_prefix(',').unwrap_or(items);
+            }
+            Value::Array(values)
+        } else {
+            let (value, after) = parse_str(rest).map_err(|v| err(&v))?;
+            end_of_line(after).map_err(|v| err(&v))?;
+            Value::Str(value)
+        };
+        entries.push((line_no, table.clone(), key.clone(), value));
+    }
+    Ok(entries)
+}
+
+/// Parse keys separated by dots, bare or quoted, returns them and the rest of the line
+fn parse_keys(s: &str) -> Result<(Vec<String>, &str), String> {
+    let mut keys = vec![];
+    let mut rest = s.trim_start();
+    loop {
+        let (key, after) = if rest.starts_with(['"', '\'']) {
+            parse_str(rest)?
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err("expected a key".to_string());
+            }
+            (rest[..end].to_string(), &rest[end..])
+        };
+        keys.push(key);
+        rest = after.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after.trim_start(),
+            None => return Ok((keys, rest)),
+        }
+    }
+}
+
+/// Parse a basic or literal string, returns it and the rest of the line
+fn parse_str(s: &str) -> Result<(String, &str), String> {
+    if let Some(rest) = s.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unclosed string")?;
+        return Ok((rest[..end].to_string(), &rest[end + 1..]));
+    }
+    let rest = s.strip_prefix('"').ok_or("expected a string")?;
+    let mut value = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &rest[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                _ => return Err("unsupported escape".to_string()),
+            },
+            c => value.push(c),
+        }
+    }
+    Err("unclosed string".to_string())
+}
+
+fn end_of_line(s: &str) -> Result<(), String> {
+    let s = s.trim_start();
+    if s.is_empty() || s.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("unexpected '{s}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+# workstation
+packages = [
+    "git", "ripgrep",
+    "fd",  # find
+    'python3.11',
+]
+
+[pins]
+ripgrep = "14.1.0"
+
+[os.debian]
+packages = ["build-essential"]
+exclude = ["python3.11"]
+
+[os.debian.rename]
+fd = "fd-bin"
+
+[vendor.apt.rename]
+fd = "fd-find"
+"#;
+
+    /// `apt-mark showmanual` of a stock debian 12 cloud image with git and htop added
+    const SHOWMANUAL: &str = "\
+adduser\n\
+apt\n\
+apt-utils\n\
+base-files\n\
+base-passwd\n\
+bash\n\
+bsdutils\n\
+ca-certificates\n\
+coreutils\n\
+cron\n\
+dash\n\
+debconf\n\
+debian-archive-keyring\n\
+debianutils\n\
+diffutils\n\
+dmidecode\n\
+dpkg\n\
+e2fsprogs\n\
+fdisk\n\
+findutils\n\
+git\n\
+gpgv\n\
+grep\n\
+grub-cloud-amd64\n\
+gzip\n\
+hostname\n\
+htop\n\
+ifupdown\n\
+init\n\
+init-system-helpers\n\
+iproute2\n\
+iputils-ping\n\
+isc-dhcp-client\n\
+kmod\n\
+less\n\
+libc-bin\n\
+linux-image-cloud-amd64\n\
+login\n\
+logrotate\n\
+mawk\n\
+mount\n\
+nano\n\
+ncurses-base\n\
+ncurses-bin\n\
+netbase\n\
+openssh-server\n\
+passwd\n\
+perl-base\n\
+procps\n\
+sed\n\
+sensible-utils\n\
+sudo\n\
+systemd\n\
+systemd-sysv\n\
+sysvinit-utils\n\
+tar\n\
+tzdata\n\
+udev\n\
+unattended-upgrades\n\
+util-linux\n\
+vim-tiny\n\
+wget\n\
+whiptail\n";
+
+    /// `dpkg-query -W -f '${Package} ${Essential} ${Priority}\n'` of the packages above
+    const DPKG_QUERY: &str = "\
+adduser  important\n\
+apt  required\n\
+apt-utils  important\n\
+base-files yes required\n\
+base-passwd yes required\n\
+bash yes required\n\
+bsdutils yes required\n\
+ca-certificates  optional\n\
+coreutils yes required\n\
+cron  important\n\
+dash yes required\n\
+debconf  required\n\
+debian-archive-keyring  important\n\
+debianutils yes required\n\
+diffutils yes required\n\
+dmidecode  important\n\
+dpkg yes required\n\
+e2fsprogs  required\n\
+fdisk  important\n\
+findutils yes required\n\
+git  optional\n\
+gpgv  important\n\
+grep yes required\n\
+grub-cloud-amd64  optional\n\
+gzip yes required\n\
+hostname yes required\n\
+htop  optional\n\
+ifupdown  important\n\
+init  required\n\
+init-system-helpers yes required\n\
+iproute2  important\n\
+iputils-ping  important\n\
+isc-dhcp-client  important\n\
+kmod  important\n\
+less  important\n\
+libc-bin yes required\n\
+linux-image-cloud-amd64  optional\n\
+login yes required\n\
+logrotate  important\n\
+mawk  required\n\
+mount  required\n\
+nano  important\n\
+ncurses-base yes required\n\
+ncurses-bin yes required\n\
+netbase  important\n\
+openssh-server  optional\n\
+passwd  required\n\
+perl-base yes required\n\
+procps  important\n\
+sed yes required\n\
+sensible-utils  required\n\
+sudo  optional\n\
+systemd  important\n\
+systemd-sysv  important\n\
+sysvinit-utils yes required\n\
+tar yes required\n\
+tzdata  required\n\
+udev  important\n\
+unattended-upgrades  optional\n\
+util-linux yes required\n\
+vim-tiny  important\n\
+wget  standard\n\
+whiptail  important\n";
+
+    fn installed(name: &str, version: &str) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            arch: None,
+            repo: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let manifest: Manifest = MANIFEST.parse().unwrap();
+        let names = |os, tool| -> Vec<String> {
+            manifest
+                .resolve(os, tool)
+                .iter()
+                .map(|v| v.to_string())
+                .collect()
+        };
+        assert_eq!(
+            names("debian", "apt"),
+            ["git", "ripgrep=14.1.0", "fd-find", "build-essential"]
+        );
+        assert_eq!(
+            names("debian", "nala"),
+            ["git", "ripgrep=14.1.0", "fd-bin", "build-essential"]
+        );
+        assert_eq!(
+            names("arch", "pacman"),
+            ["git", "ripgrep=14.1.0", "fd", "python3.11"]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let manifest: Manifest = MANIFEST.parse().unwrap();
+        let changes = manifest
+            .diff(
+                "debian",
+                "apt",
+                &[
+                    installed("git", "1:2.39.2-1.1"),
+                    installed("ripgrep", "13.0.0-4+b2"),
+                    installed("fd-find", "8.6.0-3"),
+                ],
+            )
+            .unwrap();
+        let lines: Vec<String> = changes.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            lines,
+            ["~ ripgrep 13.0.0-4+b2 -> 14.1.0", "+ build-essential"]
+        );
+
+        // A pin the installed version cannot be checked against
+        let unknown = InstalledPackage {
+            name: "ripgrep".to_string(),
+            version: None,
+            arch: None,
+            repo: None,
+        };
+        assert_eq!(
+            manifest.diff("arch", "pacman", &[unknown]).unwrap_err(),
+            UptError::InvalidManifest(
+                "cannot check the pin of 'ripgrep', pacman does not list its installed version"
+                    .to_string()
+            )
+        );
+
+        assert!(version_matches("14.1.0-1", "14.1.0"));
+        assert!(version_matches("2:9.0.1378-2", "9.0.1378"));
+        assert!(!version_matches("14.1.01", "14.1.0"));
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut manifest: Manifest = MANIFEST.parse().unwrap();
+        manifest.protect = vec!["openssh-client".to_string()];
+        let explicit = [
+            "git",
+            "fd-find",
+            "htop",
+            "sudo",
+            "openssh-client",
+            "python3.11",
+        ]
+        .map(|v| v.to_string());
+        assert_eq!(
+            manifest.prune("debian", "apt", &explicit, &[]),
+            ["htop", "python3.11"]
+        );
+        assert_eq!(
+            manifest.prune("arch", "pacman", &explicit, &["htop".to_string()]),
+            ["fd-find"]
+        );
+
+        assert!(protected("linux-image-6.1.0-18-amd64"));
+        assert!(protected("grub2-efi-x64"));
+        assert!(protected("kernel-core"));
+        assert!(!protected("initdb"));
+        assert!(!protected("libcurl4"));
+    }
+
+    #[test]
+    fn test_prune_stock_debian() {
+        // A stock debian 12 cloud image with git and htop added
+        let explicit: Vec<String> = SHOWMANUAL.lines().map(|v| v.to_string()).collect();
+        let essential = crate::installed::parse_essential("apt", DPKG_QUERY);
+        let manifest: Manifest = "packages = [\"git\"]".parse().unwrap();
+        assert_eq!(
+            manifest.prune("debian", "apt", &explicit, &essential),
+            ["htop", "unattended-upgrades", "wget"]
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let names = ["git", "fd-find", "python3-pip", "vim"].map(|v| v.to_string());
+        let manifest = Manifest::export("apt", &names);
+        assert_eq!(manifest.packages, ["git", "fd", "pip", "vim"]);
+        assert_eq!(
+            manifest.to_toml(),
+            "packages = [\n    \"git\",\n    \"fd\",\n    \"pip\",\n    \"vim\",\n]"
+        );
+        assert_eq!(
+            manifest.to_json(),
+            r#"{"packages": ["git","fd","pip","vim"]}"#
+        );
+        // Applied on another distro
+        let manifest: Manifest = manifest.to_toml().parse().unwrap();
+        let specs: Vec<String> = manifest
+            .resolve("fedora", "dnf")
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(specs, ["git", "fd-find", "python3-pip", "vim-enhanced"]);
+
+        // emerge lists the packages with their category, it takes them without one
+        let names = ["app-editors/vim", "dev-vcs/git"].map(|v| v.to_string());
+        let manifest = Manifest::export("emerge", &names);
+        assert_eq!(manifest.packages, ["vim", "git"]);
+        let changes = manifest
+            .diff(
+                "gentoo",
+                "emerge",
+                &[installed("app-editors/vim", "9.0.2167")],
+            )
+            .unwrap();
+        let lines: Vec<String> = changes.iter().map(|v| v.to_string()).collect();
+        assert_eq!(lines, ["+ git"]);
+        let explicit = [
+            "app-editors/vim",
+            "app-misc/htop",
+            "sys-kernel/linux-firmware",
+        ]
+        .map(|v| v.to_string());
+        assert_eq!(
+            manifest.prune("gentoo", "emerge", &explicit, &[]),
+            ["app-misc/htop"]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = |s: &str| s.parse::<Manifest>().unwrap_err().to_string();
+        assert_eq!(
+            err("packages = [\"vim\"\n[pins"),
+            "Invalid manifest, line 2: expected a string"
+        );
+        assert_eq!(
+            err("pkgs = [\"vim\"]"),
+            "Invalid manifest, line 1: unexpected key 'pkgs'"
+        );
+        assert_eq!(
+            err("[vendor.foo.rename]\nvim = \"vim-nox\""),
+            "Invalid manifest, line 2: unknown tool 'foo'"
+        );
+        // A misspelled key is not taken as a rename
+        assert_eq!(
+            err("[os.debian]\nexlude = \"vim\""),
+            "Invalid manifest, line 2: unknown key 'exlude'"
+        );
+        assert_eq!(
+            err("[vendor.apt.rename]\nfd = [\"fd-find\"]"),
+            "Invalid manifest, line 2: expected a string"
+        );
+        assert_eq!(
+            err("packages = [\"vim\""),
+            "Invalid manifest, line 1: unclosed array"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use crate::error::UptError;
+use crate::exit::ExitCode;
+use crate::format::{json_array, json_str};
+use crate::runner::{Capture, CommandRunner};
+use crate::signal::{pending_signal, signal_code};
+use crate::task::Task;
+use crate::utils::shell_join;
+use crate::vendor::Vendor;
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What upt decided to do for a command line: the os, the tool, the task and the commands to run
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Plan {
+    /// Detected os, empty if unknown
+    pub os: String,
+    /// The package management tool performing the task
+    pub tool: String,
+    /// The task of the command line
+    pub task: Task,
+    /// Commands run one after another
+    pub steps: Vec<Step>,
+}
+
+/// A command of the plan
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Step {
+    /// The action of the tool the step performs, e.g. `update_index`
+    pub action: &'static str,
+    /// The command to run, the first element is the program
+    pub argv: Vec<String>,
+    /// Whether a failure stops the plan
+    pub on_failure: OnFailure,
+    /// Path of the program, set by [`Plan::locate`]
+    pub path: Option<PathBuf>,
+}
+
+/// What to do when a step fails
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum OnFailure {
+    /// Stop and exit with the code of the step
+    Abort,
+    /// Go on with the next step
+    Continue,
+}
+
+impl Step {
+    /// The argv to run, with the located path of the program if any
+    pub fn command(&self) -> Vec<String> {
+        let mut argv = self.argv.clone();
+        if let Some(path) = &self.path {
+            argv[0] = path.to_string_lossy().to_string();
+        }
+        argv
+    }
+}
+
+impl OnFailure {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OnFailure::Abort => "abort",
+            OnFailure::Continue => "continue",
+        }
+    }
+}
+
+impl Plan {
+    /// The plan of the task on the os, see [`Vendor::eval`]
+    pub fn new(os: &str, tool: &Vendor, task: Task) -> Result<Self, UptError> {
+        let mut plan = tool.eval(&task)?;
+        plan.os = os.to_string();
+        Ok(plan)
+    }
+
+    /// Sync the packages index before installing, the install goes on if the sync fails.
+    ///
+    /// Nothing is added if the tool has no index to sync.
+    pub fn refresh(&mut self, tool: &Vendor) -> Result<(), UptError> {
+        if !matches!(self.task, Task::Install { .. }) {
+            return Err(UptError::InvalidArgs(
+                "--refresh only applies to install.".to_string(),
+            ));
+        }
+        if let Ok(argv) = tool.argv(&Task::UpdateIndex) {
+            let step = Step {
+                action: "update_index",
+                argv,
+                on_failure: OnFailure::Continue,
+                path: None,
+            };
+            self.steps.insert(0, step);
+        }
+        Ok(())
+    }
+
+    /// Have the last step print json if the tool can, for its output to be parsed rather than its text
+    pub fn structured(&mut self, tool: &Vendor) {
+        if let (Some(argv), Some(step)) = (tool.json_argv(&self.task), self.steps.last_mut()) {
+            if step.action == self.task.kind() {
+                step.argv = argv;
+                step.path = None;
+            }
+        }
+    }
+
+    /// Locate the programs of all steps, so a missing one fails the plan before anything runs.
+    ///
+    /// `detected` is the path of the tool's binary if it is known already.
+    /// The steps then run the located programs.
+    pub fn locate(
+        &mut self,
+        tool: &Vendor,
+        runner: &dyn CommandRunner,
+        detected: Option<&Path>,
+    ) -> Result<(), UptError> {
+        for step in self.steps.iter_mut() {
+            let path = match detected {
+                Some(path) if path.file_stem() == Some(OsStr::new(&step.argv[0])) => {
+                    path.to_path_buf()
+                }
+                _ => tool.locate_with(runner, &step.argv[0])?,
+            };
+            step.path = Some(path);
+        }
+        Ok(())
+    }
+
+    /// Run the steps with the runner, returns upt's exit code and the stdout of the last step.
+    ///
+    /// The stdout is only captured if `capture` is true,
+    /// a search or info then fails with [`ExitCode::PackageNotFound`] if nothing is parsed from it.
+    pub fn run(
+        &self,
+        tool: &Vendor,
+        runner: &mut dyn CommandRunner,
+        capture: bool,
+    ) -> io::Result<(i32, Vec<u8>)> {
+        let mut code = ExitCode::Success;
+        let mut captured = vec![];
+        for (i, step) in self.steps.iter().enumerate() {
+            // Interrupted between two steps, the signal had no command to reach
+            if let Some(code) = pending_signal() {
+                return Ok((code, captured));
+            }
+            // The output of the last step is parsed and rendered in the format,
+            // the stderr is only needed to tell a denied command
+            let capture = if capture && i + 1 == self.steps.len() {
+                Capture::Stdout
+            } else if tool.may_deny(step.action) {
+                Capture::Stderr
+            } else {
+                Capture::Nothing
+            };
+            let output = runner.run(&step.command(), &[], None, capture)?;
+            captured = output.stdout;
+            // Interrupted by a signal, the rest of the steps are not run either
+            if let Some(code) = signal_code(&output.status) {
+                return Ok((code, captured));
+            }
+            code = tool.exit_code(step.action, output.status.code().unwrap_or_default());
+            if code == ExitCode::Failure && tool.denied(&output.stderr) {
+                code = ExitCode::PermissionDenied;
+            }
+            // Tools such as apt exit 0 when nothing is found
+            if code == ExitCode::Success
+                && capture == Capture::Stdout
+                && nothing_found(tool, step.action, &captured)
+            {
+                code = ExitCode::PackageNotFound;
+            }
+            // Updates found by a sync before the last step are not the result of the task
+            if code == ExitCode::UpdatesAvailable && i + 1 < self.steps.len() {
+                code = ExitCode::Success;
+            }
+            if code != ExitCode::Success {
+                if step.on_failure == OnFailure::Abort {
+                    return Ok((code.code(), captured));
+                }
+                eprintln!(
+                    "Warning: '{}' failed, continue with the next step.",
+                    step.argv.join(" ")
+                );
+            }
+        }
+        Ok((code.code(), captured))
+    }
+
+    /// Render the plan as a json object
+    pub fn to_json(&self) -> String {
+        let os = if self.os.is_empty() {
+            "null".to_string()
+        } else {
+            json_str(&self.os)
+        };
+        let confirm = match self.task.confirm() {
+            Some(v) => v.to_string(),
+            None => "null".to_string(),
+        };
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|v| {
+                format!(
+                    "    {{\"action\": {}, \"argv\": {}, \"on_failure\": {}}}",
+                    json_str(v.action),
+                    json_array(&v.argv),
+                    json_str(v.on_failure.as_str())
+                )
+            })
+            .collect();
+        [
+            "{".to_string(),
+            format!("  \"os\": {os},"),
+            format!("  \"tool\": {},", json_str(&self.tool)),
+            format!(
+                "  \"task\": {{\"kind\": {}, \"packages\": {}, \"confirm\": {confirm}}},",
+                json_str(self.task.kind()),
+                json_array(&self.task.packages())
+            ),
+            "  \"steps\": [".to_string(),
+            steps.join(",\n"),
+            "  ]".to_string(),
+            "}".to_string(),
+        ]
+        .join("\n")
+    }
+}
+
+/// Whether the captured output of a search or info lists no package
+fn nothing_found(tool: &Vendor, action: &str, output: &[u8]) -> bool {
+    let output = String::from_utf8_lossy(output);
+    match action {
+        "search" => tool.parse_search(&output).is_empty(),
+        "info" => tool.parse_info(&output).is_empty(),
+        _ => false,
+    }
+}
+
+/// The command lines of the steps, quoted for the shell
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.steps.iter().map(|v| shell_join(&v.argv)).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::PackageSpec;
+    use crate::vendor::init_vendor;
+
+    #[test]
+    fn test_refresh() {
+        let apk = init_vendor("apk").unwrap();
+        let task = Task::Install {
+            pkgs: vec![PackageSpec::new("vim")],
+            confirm: false,
+        };
+        let mut plan = Plan::new("alpine", &apk, task).unwrap();
+        plan.refresh(&apk).unwrap();
+        assert_eq!(plan.to_string(), "apk update\napk add vim");
+        assert_eq!(plan.steps[0].on_failure, OnFailure::Continue);
+        assert_eq!(plan.steps[1].on_failure, OnFailure::Abort);
+    }
+
+    #[test]
+    fn test_structured() {
+        let brew = init_vendor("brew").unwrap();
+        let task = Task::Info {
+            pkgs: vec![PackageSpec::new("vim")],
+        };
+        let mut plan = Plan::new("macos", &brew, task.clone()).unwrap();
+        plan.structured(&brew);
+        assert_eq!(plan.to_string(), "brew info --json=v2 vim");
+        let apt = init_vendor("apt").unwrap();
+        let mut plan = Plan::new("debian", &apt, task).unwrap();
+        plan.structured(&apt);
+        assert_eq!(plan.to_string(), "apt show vim");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let apt = init_vendor("apt").unwrap();
+        let task = init_vendor("upt")
+            .unwrap()
+            .parse(
+                &["upt", "install", "-y", "vim", "jq"].map(|v| v.to_string()),
+                "apt",
+            )
+            .unwrap();
+        let plan = Plan::new("debian", &apt, task).unwrap();
+        assert_eq!(plan.to_string(), "apt install vim jq -y");
+        assert_eq!(
+            plan.to_json(),
+            r#"{
+  "os": "debian",
+  "tool": "apt",
+  "task": {"kind": "install", "packages": ["vim","jq"], "confirm": true},
+  "steps": [
+    {"action": "install", "argv": ["apt","install","vim","jq","-y"], "on_failure": "abort"}
+  ]
+}"#
+        );
+
+        let mut plan = Plan::new("", &apt, Task::ListInstalled).unwrap();
+        assert!(plan.refresh(&apt).is_err());
+        assert!(plan.to_json().contains("\"os\": null,"));
+        assert!(plan
+            .to_json()
+            .contains(r#"{"kind": "list_installed", "packages": [], "confirm": null}"#));
+    }
+}
@@ -0,0 +1,440 @@
+use crate::signal::wait_child;
+use crate::utils::{detect_os, parse_which, which_line};
+
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::thread;
+
+/// The output of a command returned by a runner, the rest goes to upt's terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Capture {
+    /// Nothing, the command keeps the terminal
+    #[default]
+    Nothing,
+    /// The stdout, to be parsed
+    Stdout,
+    /// A copy of the stderr, which is still shown, to explain a failure
+    Stderr,
+}
+
+/// Runs the commands upt plans, implement it to run them elsewhere or to only record them
+pub trait CommandRunner {
+    /// Run the command, the first element of argv is the program, its path once located.
+    ///
+    /// `stdin` is written to the command if given, otherwise it inherits upt's.
+    /// The captured stream is returned in the output, the other one is inherited and left empty.
+    /// A runner may leave the stderr empty, e.g. when the command cannot be denied.
+    fn run(
+        &mut self,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Option<&[u8]>,
+        capture: Capture,
+    ) -> io::Result<Output>;
+
+    /// Path of the program, None if it is not installed
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        which::which(program).ok()
+    }
+
+    /// Paths of the programs in order, a runner whose lookups are costly finds them all at once
+    fn which_all(&self, programs: &[&str]) -> Vec<Option<PathBuf>> {
+        programs.iter().map(|v| self.which(v)).collect()
+    }
+
+    /// The os the commands run on, e.g. `debian`
+    fn detect_os(&self) -> Option<String> {
+        detect_os()
+    }
+}
+
+/// Runs the commands on this machine, signals sent to upt are forwarded to them
+#[derive(Debug, Default)]
+pub struct LocalRunner;
+
+impl CommandRunner for LocalRunner {
+    fn run(
+        &mut self,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Option<&[u8]>,
+        capture: Capture,
+    ) -> io::Result<Output> {
+        // Root is never denied, the command keeps its terminal for progress bars and colors
+        #[cfg(unix)]
+        let capture = match capture {
+            Capture::Stderr if unsafe { libc::geteuid() } == 0 => Capture::Nothing,
+            v => v,
+        };
+        spawn(argv, env, stdin, capture)
+    }
+}
+
+/// Run the command on this machine as asked, for runners wrapping it in another program
+pub(crate) fn spawn(
+    argv: &[String],
+    env: &[(String, String)],
+    stdin: Option<&[u8]>,
+    capture: Capture,
+) -> io::Result<Output> {
+    // Resolved like `which` does, so scripts such as scoop.cmd are found on windows,
+    // located programs are run as they are
+    let program = match PathBuf::from(&argv[0]) {
+        path if path.is_absolute() => path,
+        _ => LocalRunner
+            .which(&argv[0])
+            .unwrap_or_else(|| argv[0].clone().into()),
+    };
+    let mut command = Command::new(program);
+    command.args(&argv[1..]);
+    command.envs(env.iter().map(|(k, v)| (k, v)));
+    if stdin.is_some() {
+        command.stdin(Stdio::piped());
+    }
+    match capture {
+        Capture::Stdout => command.stdout(Stdio::piped()),
+        Capture::Stderr => command.stderr(Stdio::piped()),
+        Capture::Nothing => &mut command,
+    };
+    let mut child = command.spawn()?;
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| thread::spawn(|| tee_stderr(pipe)));
+    // Written from a thread, a command filling its stdout before reading all its stdin
+    // would otherwise wait for upt while upt waits for it
+    let (output, written) = thread::scope(|s| {
+        let writer = match (stdin, child.stdin.take()) {
+            (Some(input), Some(mut pipe)) => Some(s.spawn(move || pipe.write_all(input))),
+            _ => None,
+        };
+        let output = wait_child(child);
+        let written = writer.map_or(Ok(()), |v| v.join().unwrap_or(Ok(())));
+        (output, written)
+    });
+    let mut output = output?;
+    match written {
+        // The command exited without reading all of it
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        v => v?,
+    }
+    if let Some(handle) = stderr {
+        output.stderr = handle.join().unwrap_or_default();
+    }
+    Ok(output)
+}
+
+/// Copy the stderr of a command to upt's as it comes, returns its beginning
+fn tee_stderr(mut pipe: impl Read) -> Vec<u8> {
+    const KEPT: usize = 64 * 1024;
+    let mut kept = vec![];
+    let mut buf = [0; 8192];
+    while let Ok(n) = pipe.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        let _ = io::stderr().write_all(&buf[..n]);
+        let room = KEPT.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+    }
+    kept
+}
+
+/// Run a command wrapping one that runs elsewhere, returns its stdout if it succeeds
+pub(crate) fn query(argv: &[String]) -> Option<String> {
+    let output = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Whether a command run through a wrapper such as ssh gets a tty, for the tool's prompts.
+///
+/// A captured output must not be mangled by the tty.
+pub(crate) fn wants_tty(stdin: Option<&[u8]>, capture: Capture) -> bool {
+    capture == Capture::Nothing && stdin.is_none() && io::stdin().is_terminal()
+}
+
+/// The stdout of a shell line run by the wrapper, which takes it last, e.g. `ssh -T -- host`
+pub(crate) fn query_line(prefix: &[String], line: &str) -> Option<String> {
+    let mut argv = prefix.to_vec();
+    argv.push(line.to_string());
+    query(&argv)
+}
+
+/// Paths of the programs on the other side of the wrapper, looked up with a single command
+pub(crate) fn which_through(prefix: &[String], programs: &[&str]) -> Vec<Option<PathBuf>> {
+    parse_which(query_line(prefix, &which_line(programs)), programs.len())
+}
+
+/// A command received by a [`RecordingRunner`]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RecordedCommand {
+    /// The command, the first element is the program
+    pub argv: Vec<String>,
+    /// Variables set for the command
+    pub env: Vec<(String, String)>,
+    /// What was written to its stdin, if anything
+    pub stdin: Option<Vec<u8>>,
+}
+
+/// Records the commands instead of running them, for tests.
+///
+/// Every command succeeds with an empty output unless a response is given for it.
+///
+/// ```
+/// use upt::{init_vendor, Plan, RecordingRunner, Task};
+///
+/// let apt = init_vendor("apt").unwrap();
+/// let plan = Plan::new("debian", &apt, Task::ListInstalled).unwrap();
+/// let mut runner = RecordingRunner::new();
+/// runner.respond(&["apt", "list"], 0, "vim/stable,now 2:9.0.1378-2 amd64 [installed]\n");
+/// let (code, stdout) = plan.run(&apt, &mut runner, true).unwrap();
+/// assert_eq!(code, 0);
+/// assert_eq!(apt.parse_installed(&String::from_utf8_lossy(&stdout))[0].name, "vim");
+/// assert_eq!(runner.commands[0].argv, ["apt", "list", "-i"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    /// Commands received so far, in order
+    pub commands: Vec<RecordedCommand>,
+    responses: Vec<Response>,
+    missing: Vec<String>,
+    os: Option<String>,
+}
+
+/// The command prefix, the exit code, the stdout and the stderr
+type Response = (Vec<String>, i32, Vec<u8>, Vec<u8>);
+
+impl RecordingRunner {
+    /// A runner with no command recorded and no response
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Exit with the code and print the stdout for the commands starting with the words.
+    ///
+    /// The first word is the name of the program, it also matches the program once located.
+    pub fn respond(&mut self, prefix: &[&str], code: i32, stdout: &str) {
+        self.respond_stderr(prefix, code, stdout, "");
+    }
+
+    /// Like [`respond`](Self::respond), the stderr is returned in the output as well
+    pub fn respond_stderr(&mut self, prefix: &[&str], code: i32, stdout: &str, stderr: &str) {
+        let prefix = prefix.iter().map(|v| v.to_string()).collect();
+        self.responses.push((
+            prefix,
+            code,
+            stdout.as_bytes().to_vec(),
+            stderr.as_bytes().to_vec(),
+        ));
+    }
+
+    /// Report the program as not installed
+    pub fn missing(&mut self, program: &str) {
+        self.missing.push(program.to_string());
+    }
+
+    /// Report the os the commands run on, it is unknown otherwise
+    pub fn os(&mut self, os: &str) {
+        self.os = Some(os.to_string());
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(
+        &mut self,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Option<&[u8]>,
+        capture: Capture,
+    ) -> io::Result<Output> {
+        self.commands.push(RecordedCommand {
+            argv: argv.to_vec(),
+            env: env.to_vec(),
+            stdin: stdin.map(|v| v.to_vec()),
+        });
+        let (code, stdout, stderr) = self
+            .responses
+            .iter()
+            .find(|(prefix, _, _, _)| starts_with(argv, prefix))
+            .map(|(_, code, stdout, stderr)| (*code, stdout.clone(), stderr.clone()))
+            .unwrap_or_default();
+        Ok(Output {
+            status: exit_status(code),
+            stdout: if capture == Capture::Stdout {
+                stdout
+            } else {
+                vec![]
+            },
+            stderr: if capture == Capture::Stderr {
+                stderr
+            } else {
+                vec![]
+            },
+        })
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        if self.missing.iter().any(|v| v == program) {
+            None
+        } else {
+            Some(PathBuf::from(format!("/usr/bin/{program}")))
+        }
+    }
+
+    fn detect_os(&self) -> Option<String> {
+        self.os.clone()
+    }
+}
+
+/// Whether the argv starts with the words, a located program matches its name
+fn starts_with(argv: &[String], prefix: &[String]) -> bool {
+    match (argv.split_first(), prefix.split_first()) {
+        (Some((program, args)), Some((name, words))) => {
+            let stem = Path::new(program).file_name().and_then(|v| v.to_str());
+            (program == name || stem == Some(name)) && args.starts_with(words)
+        }
+        _ => prefix.is_empty(),
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw((code & 0xff) << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+    use crate::task::{PackageSpec, Task};
+    use crate::vendor::init_vendor;
+
+    #[test]
+    fn test_recording_runner() {
+        let apk = init_vendor("apk").unwrap();
+        let task = Task::Install {
+            pkgs: vec![PackageSpec::new("vim")],
+            confirm: false,
+        };
+        let mut plan = Plan::new("alpine", &apk, task).unwrap();
+        plan.refresh(&apk).unwrap();
+
+        // A failed refresh goes on with the install
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apk", "update"], 1, "");
+        assert_eq!(plan.run(&apk, &mut runner, false).unwrap().0, 0);
+        let argvs: Vec<_> = runner.commands.iter().map(|v| v.argv.join(" ")).collect();
+        assert_eq!(argvs, ["apk update", "apk add vim"]);
+
+        // A failed install stops the plan
+        let mut runner = RecordingRunner::new();
+        runner.respond(&["apk", "update"], 1, "");
+        runner.respond(&["apk", "add"], 1, "");
+        assert_eq!(plan.run(&apk, &mut runner, false).unwrap().0, 1);
+
+        // apt fails with 100 for any error, the message tells it needs root
+        let apt = init_vendor("apt").unwrap();
+        let task = Task::Install {
+            pkgs: vec![PackageSpec::new("vim")],
+            confirm: false,
+        };
+        let install = Plan::new("debian", &apt, task).unwrap();
+        let mut denied = RecordingRunner::new();
+        let message = "E: Could not open lock file /var/lib/dpkg/lock-frontend - open (13: Permission denied)\n\
+                       E: Unable to acquire the dpkg frontend lock, are you root?\n";
+        denied.respond_stderr(&["apt"], 100, "", message);
+        assert_eq!(install.run(&apt, &mut denied, false).unwrap().0, 5);
+        let mut missing = RecordingRunner::new();
+        missing.respond_stderr(&["apt"], 100, "", "E: Unable to locate package vim\n");
+        assert_eq!(install.run(&apt, &mut missing, false).unwrap().0, 1);
+
+        // dnf check-update exits 100 when updates are available, it is not a failed refresh
+        let dnf = init_vendor("dnf").unwrap();
+        let task = Task::Install {
+            pkgs: vec![PackageSpec::new("vim")],
+            confirm: false,
+        };
+        let mut refreshed = Plan::new("fedora", &dnf, task).unwrap();
+        refreshed.refresh(&dnf).unwrap();
+        let mut updates = RecordingRunner::new();
+        updates.respond(&["dnf", "check-update"], 100, "");
+        assert_eq!(refreshed.run(&dnf, &mut updates, false).unwrap().0, 0);
+        let update = Plan::new("fedora", &dnf, Task::UpdateIndex).unwrap();
+        assert_eq!(update.run(&dnf, &mut updates, false).unwrap().0, 4);
+
+        // apt search exits 0 when nothing matches, its output tells
+        let task = Task::Search {
+            query: "vim".to_string(),
+        };
+        let search = Plan::new("debian", &apt, task).unwrap();
+        let mut found = RecordingRunner::new();
+        let results = "vim/stable 2:9.0.1378-2 amd64\n  Vi IMproved - enhanced vi editor\n";
+        found.respond(&["apt", "search"], 0, results);
+        assert_eq!(
+            search.run(&apt, &mut found, true).unwrap(),
+            (0, results.into())
+        );
+        let mut nothing = RecordingRunner::new();
+        nothing.respond(&["apt", "search"], 0, "\n");
+        assert_eq!(search.run(&apt, &mut nothing, true).unwrap().0, 2);
+
+        // Located steps run the located programs
+        let mut located = RecordingRunner::new();
+        located.respond(&["apk", "add"], 1, "");
+        let detected = PathBuf::from("/sbin/apk");
+        plan.locate(&apk, &located, Some(&detected)).unwrap();
+        assert_eq!(plan.run(&apk, &mut located, false).unwrap().0, 1);
+        let argvs: Vec<_> = located.commands.iter().map(|v| v.argv.join(" ")).collect();
+        assert_eq!(argvs, ["/sbin/apk update", "/sbin/apk add vim"]);
+
+        runner.missing("apk");
+        assert_eq!(
+            plan.locate(&apk, &runner, None),
+            Err(crate::error::UptError::NoCommand("apk".to_string(), None))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_local_runner() {
+        let argv =
+            ["sh", "-c", "read v; echo \"$v $GREETING\"; echo done >&2"].map(|v| v.to_string());
+        let env = [("GREETING".to_string(), "world".to_string())];
+        let output = LocalRunner
+            .run(&argv, &env, Some(b"hello\n"), Capture::Stdout)
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello world\n");
+        assert!(output.stderr.is_empty());
+
+        // The stderr is copied, root's is left alone by the local runner
+        let output = spawn(&argv, &env, Some(b"hello\n"), Capture::Stderr).unwrap();
+        assert!(output.stdout.is_empty());
+        assert_eq!(output.stderr, b"done\n");
+
+        // A large stdin is echoed back while it is still written
+        let argv = ["cat"].map(|v| v.to_string());
+        let input = vec![b'x'; 1 << 20];
+        let output = LocalRunner
+            .run(&argv, &[], Some(&input), Capture::Stdout)
+            .unwrap();
+        assert_eq!(output.stdout, input);
+    }
+}
@@ -0,0 +1,436 @@
+use crate::format::{json_lines, json_opt, json_str, render_table, Format};
+use crate::installed::{slackware_package, split_release, split_version, table, RPM_ARCHS};
+
+/// A package found by the `search` action of a tool
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct SearchResult {
+    /// Name of the package
+    pub name: String,
+    /// Version available
+    pub version: Option<String>,
+    /// The repository providing it
+    pub repo: Option<String>,
+    /// Summary of the package
+    pub description: Option<String>,
+    /// `None` if the tool does not tell
+    pub installed: Option<bool>,
+}
+
+impl SearchResult {
+    fn new(name: &str, version: Option<&str>) -> Self {
+        SearchResult {
+            name: name.to_string(),
+            version: version.map(|v| v.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn describe(mut self, description: Option<&str>) -> Self {
+        self.description = description.and_then(text);
+        self
+    }
+}
+
+/// Render the results as a table of name, version, repo, installed and description
+pub fn render_search(results: &[SearchResult], format: Format) -> String {
+    if format == Format::Json {
+        let lines = results
+            .iter()
+            .map(|v| {
+                format!(
+                    "  {{\"name\":{},\"version\":{},\"repo\":{},\"description\":{},\"installed\":{}}}",
+                    json_str(&v.name),
+                    json_opt(&v.version),
+                    json_opt(&v.repo),
+                    json_opt(&v.description),
+                    v.installed
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "null".to_string()),
+                )
+            })
+            .collect();
+        return json_lines(lines);
+    }
+    let columns = [
+        ("name", "Name"),
+        ("version", "Version"),
+        ("repo", "Repo"),
+        ("installed", "Installed"),
+        ("description", "Description"),
+    ];
+    let rows: Vec<Vec<Option<String>>> = results
+        .iter()
+        .map(|v| {
+            vec![
+                Some(v.name.clone()),
+                v.version.clone(),
+                v.repo.clone(),
+                v.installed
+                    .map(|v| if v { "yes" } else { "no" }.to_string()),
+                v.description.clone(),
+            ]
+        })
+        .collect();
+    render_table(&columns, &rows, format)
+}
+
+/// Parse the output of the `search` action of the tool
+pub(crate) fn parse_search(tool: &str, output: &str) -> Vec<SearchResult> {
+    let lines = output
+        .lines()
+        .map(|v| v.trim_end())
+        .filter(|v| !v.is_empty());
+    match tool {
+        // vim/stable 2:9.0.1378-2 amd64 [installed]
+        //   Vi IMproved - enhanced vi editor
+        "apt" | "pkg(termux)" => two_lines(lines, |line| {
+            let mut fields = line.split_whitespace();
+            let (name, repo) = fields.next()?.split_once('/')?;
+            let mut result = SearchResult::new(name, fields.next());
+            result.repo = repo.split(',').next().map(|v| v.to_string());
+            result.installed = Some(line.contains("[installed"));
+            Some(result)
+        }),
+        // extra/vim 9.1.0-1 (group) [installed]
+        //     Vi Improved, a highly configurable, improved version of the vi text editor
+        "pacman" | "paru" | "yay" => two_lines(lines, |line| {
+            let mut fields = line.split_whitespace();
+            let (repo, name) = fields.next()?.split_once('/')?;
+            let mut result = SearchResult::new(name, fields.next());
+            result.repo = Some(repo.to_string());
+            let lower = line.to_lowercase();
+            result.installed = Some(lower.contains("[installed") || lower.contains("(installed"));
+            Some(result)
+        }),
+        // vim @9.0.2167 (editors)
+        //     Vi "workalike" with many additional features
+        "port" => two_lines(lines, |line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let version = fields.next()?.strip_prefix('@')?;
+            Some(SearchResult::new(name, Some(version)))
+        }),
+        // vim-enhanced.x86_64 : A version of the VIM editor which includes recent enhancements
+        "dnf" | "yum" => lines
+            .filter(|v| !v.starts_with('='))
+            .filter_map(|v| {
+                let (name, description) =
+                    v.split_once(" : ").or_else(|| v.trim().split_once('\t'))?;
+                let name = name.trim();
+                let name = match name.rsplit_once('.') {
+                    Some((name, arch)) if RPM_ARCHS.contains(&arch) => name,
+                    _ => name,
+                };
+                Some(SearchResult::new(name, None).describe(Some(description)))
+            })
+            .collect(),
+        // i+ | vim | Vi IMproved | package
+        "zypper" => lines
+            .filter_map(|v| {
+                let fields: Vec<&str> = v.split('|').map(|v| v.trim()).collect();
+                match fields.as_slice() {
+                    [status, name, summary, ..] if *name != "Name" => {
+                        let mut result = SearchResult::new(name, None).describe(Some(summary));
+                        result.installed = Some(status.starts_with('i'));
+                        Some(result)
+                    }
+                    _ => None,
+                }
+            })
+            .collect(),
+        "brew" => lines
+            .filter(|v| !v.starts_with("==>"))
+            .flat_map(|v| v.split_whitespace())
+            .map(|v| SearchResult::new(v, None))
+            .collect(),
+        // vim-9.0.2073-r0 - Improved vi-style text editor
+        "apk" => lines
+            .filter_map(|v| {
+                let (pkg, description) = match v.split_once(" - ") {
+                    Some((pkg, description)) => (pkg, Some(description)),
+                    None => (v, None),
+                };
+                let (name, version) = split_release(pkg.trim())?;
+                Some(SearchResult::new(name, Some(&version)).describe(description))
+            })
+            .collect(),
+        "emerge" => emerge(lines),
+        "flatpak" => lines
+            .filter_map(|v| {
+                let fields: Vec<&str> = v.split('\t').collect();
+                let mut result = SearchResult::new(fields.get(2)?, fields.get(3).copied())
+                    .describe(fields.get(1).copied());
+                result.repo = fields.get(5).map(|v| v.to_string());
+                Some(result)
+            })
+            .collect(),
+        "snap" => {
+            // The header is not underlined
+            let mut lines = lines;
+            let header = lines.next();
+            table(
+                header.into_iter().chain(["---"]).chain(lines),
+                &["Name", "Version", "Summary"],
+            )
+            .into_iter()
+            .map(|row| SearchResult::new(&row[0], Some(&row[1])).describe(Some(&row[2])))
+            .collect()
+        }
+        "scoop" => table(lines, &["Name", "Version", "Source"])
+            .into_iter()
+            .map(|row| {
+                let mut result = SearchResult::new(&row[0], Some(&row[1]));
+                result.repo = Some(row[2].clone()).filter(|v| !v.is_empty());
+                result
+            })
+            .collect(),
+        "winget" => table(lines, &["Id", "Version", "Source"])
+            .into_iter()
+            .map(|row| {
+                let mut result = SearchResult::new(&row[0], Some(&row[1]));
+                result.repo = Some(row[2].clone()).filter(|v| !v.is_empty());
+                result
+            })
+            .collect(),
+        "choco" => lines
+            .filter(|v| !v.starts_with("Chocolatey v") && !v.ends_with(" packages found."))
+            .filter_map(|v| {
+                let mut fields = v.split_whitespace();
+                Some(SearchResult::new(fields.next()?, fields.next()))
+            })
+            .collect(),
+        // [*] vim-9.0.2116_1  Vim editor (vi clone)
+        "xbps" => lines
+            .filter_map(|v| {
+                let installed = v.starts_with("[*]");
+                let v = v.get(3..)?;
+                let (name, version) = v.split_whitespace().next()?.rsplit_once('-')?;
+                let mut result =
+                    SearchResult::new(name, Some(version)).describe(after_fields(v, 1));
+                result.installed = Some(installed);
+                Some(result)
+            })
+            .collect(),
+        // nixpkgs.vim  vim-9.0.2116
+        "nix-env" => lines
+            .filter_map(|v| {
+                let (name, version) = split_version(v.split_whitespace().nth(1)?);
+                Some(SearchResult::new(name, version))
+            })
+            .collect(),
+        // vim - 9.0-1 - Vi IMproved
+        "opkg" => lines
+            .filter_map(|v| {
+                let mut fields = v.splitn(3, " - ");
+                let name = fields.next()?;
+                Some(SearchResult::new(name, fields.next()).describe(fields.next()))
+            })
+            .collect(),
+        // vim-9.0.2153    Improved version of the vi editor
+        "pkg" => lines
+            .filter_map(|v| {
+                let (name, version) = v.split_whitespace().next()?.rsplit_once('-')?;
+                Some(SearchResult::new(name, Some(version)).describe(after_fields(v, 1)))
+            })
+            .collect(),
+        "guix" => guix(lines),
+        // vim - Vi IMproved
+        "eopkg" => lines
+            .filter_map(|v| {
+                let (name, description) = v.split_once(" - ")?;
+                Some(SearchResult::new(name.trim(), None).describe(Some(description)))
+            })
+            .collect(),
+        // [ installed ] - bash-5.2.015-x86_64-1
+        "slackpkg" => lines
+            .filter_map(|v| {
+                let (status, pkg) = v.split_once("] - ")?;
+                let pkg = slackware_package(pkg)?;
+                let mut result = SearchResult::new(&pkg.name, pkg.version.as_deref());
+                result.installed = Some(status.trim_start_matches('[').trim() == "installed");
+                Some(result)
+            })
+            .collect(),
+        _ => two_lines(lines, |line| {
+            let mut fields = line.split_whitespace();
+            Some(SearchResult::new(fields.next()?, fields.next()))
+        }),
+    }
+}
+
+/// Entries of a header line followed by an indented description
+fn two_lines<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    header: impl Fn(&str) -> Option<SearchResult>,
+) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = vec![];
+    for line in lines {
+        let description = line
+            .strip_prefix(char::is_whitespace)
+            .or_else(|| line.strip_prefix("└──"));
+        match (description, results.last_mut()) {
+            (Some(description), Some(result)) => {
+                if result.description.is_none() {
+                    result.description = text(description);
+                }
+            }
+            (Some(_), None) => {}
+            (None, _) => results.extend(header(line)),
+        }
+    }
+    results
+}
+
+/// Entries of `emerge --search`
+///
+/// ```text
+/// *  app-editors/vim
+///       Latest version available: 9.0.2167
+///       Latest version installed: [ Not Installed ]
+///       Description:   Vim, an improved vi-style text editor
+/// ```
+fn emerge<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = vec![];
+    for line in lines {
+        if let Some(name) = line.strip_prefix('*') {
+            results.push(SearchResult::new(name.trim(), None));
+            continue;
+        }
+        let (Some(result), Some((key, value))) = (results.last_mut(), line.split_once(':')) else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Latest version available" => result.version = Some(value.to_string()),
+            "Latest version installed" => result.installed = Some(value != "[ Not Installed ]"),
+            "Description" => result.description = text(value),
+            _ => {}
+        }
+    }
+    results
+}
+
+/// Records of `guix search`, fields of a record are `key: value` lines
+fn guix<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = vec![];
+    for line in lines {
+        let Some((key, value)) = line.split_once(": ") else {
+            continue;
+        };
+        match (key, results.last_mut()) {
+            ("name", _) => results.push(SearchResult::new(value, None)),
+            ("version", Some(result)) => result.version = Some(value.to_string()),
+            ("synopsis", Some(result)) => result.description = text(value),
+            _ => {}
+        }
+    }
+    results
+}
+
+fn text(v: &str) -> Option<String> {
+    Some(v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// The rest of the line after `n` whitespace separated fields
+fn after_fields(line: &str, n: usize) -> Option<&str> {
+    let mut rest = line.trim_start();
+    for _ in 0..n {
+        let i = rest.find(char::is_whitespace)?;
+        rest = rest[i..].trim_start();
+    }
+    Some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(
+        name: &str,
+        version: Option<&str>,
+        repo: Option<&str>,
+        description: Option<&str>,
+        installed: Option<bool>,
+    ) -> SearchResult {
+        SearchResult {
+            name: name.to_string(),
+            version: version.map(|v| v.to_string()),
+            repo: repo.map(|v| v.to_string()),
+            description: description.map(|v| v.to_string()),
+            installed,
+        }
+    }
+
+    #[test]
+    fn test_parse_search() {
+        assert_eq!(
+            parse_search(
+                "apt",
+                "Sorting...\nFull Text Search...\nvim/stable 2:9.0.1378-2 amd64 [installed]\n  Vi IMproved - enhanced vi editor\n\nvim-gtk3/stable 2:9.0.1378-2 amd64\n  Vi IMproved - enhanced vi editor - with GTK3 GUI\n"
+            ),
+            [
+                result("vim", Some("2:9.0.1378-2"), Some("stable"), Some("Vi IMproved - enhanced vi editor"), Some(true)),
+                result("vim-gtk3", Some("2:9.0.1378-2"), Some("stable"), Some("Vi IMproved - enhanced vi editor - with GTK3 GUI"), Some(false)),
+            ]
+        );
+        assert_eq!(
+            parse_search("pacman", "extra/vim 9.1.0-1 [installed]\n    Vi Improved\n"),
+            [result(
+                "vim",
+                Some("9.1.0-1"),
+                Some("extra"),
+                Some("Vi Improved"),
+                Some(true)
+            )]
+        );
+        assert_eq!(
+            parse_search(
+                "dnf",
+                "=== Name & Summary Matched: vim ===\nvim-enhanced.x86_64 : A version of the VIM editor\n"
+            ),
+            [result("vim-enhanced", None, None, Some("A version of the VIM editor"), None)]
+        );
+        assert_eq!(
+            parse_search("brew", "==> Formulae\nvim\nneovim\n\n==> Casks\nmacvim\n").len(),
+            3
+        );
+        assert_eq!(
+            parse_search(
+                "zypper",
+                "S  | Name | Summary     | Type\n---+------+-------------+--------\ni+ | vim  | Vi IMproved | package\n"
+            ),
+            [result("vim", None, None, Some("Vi IMproved"), Some(true))]
+        );
+        assert_eq!(
+            parse_search(
+                "emerge",
+                "*  app-editors/vim\n      Latest version available: 9.0.2167\n      Latest version installed: [ Not Installed ]\n      Description:   Vim, an improved vi-style text editor\n"
+            ),
+            [result("app-editors/vim", Some("9.0.2167"), None, Some("Vim, an improved vi-style text editor"), Some(false))]
+        );
+        assert_eq!(
+            parse_search("xbps", "[*] vim-9.0.2116_1  Vim editor (vi clone)\n"),
+            [result(
+                "vim",
+                Some("9.0.2116_1"),
+                None,
+                Some("Vim editor (vi clone)"),
+                Some(true)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_render_search() {
+        let results = [result("vim", Some("9.1"), None, Some("Vi"), Some(true))];
+        assert_eq!(
+            render_search(&results, Format::Json),
+            "[\n  {\"name\":\"vim\",\"version\":\"9.1\",\"repo\":null,\"description\":\"Vi\",\"installed\":true}\n]"
+        );
+        assert_eq!(
+            render_search(&results, Format::Table),
+            "Name  Version  Repo  Installed  Description\nvim   9.1      -     yes        Vi"
+        );
+    }
+}
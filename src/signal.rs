@@ -0,0 +1,109 @@
+use std::io;
+use std::process::{Child, ExitStatus, Output};
+
+/// Wait for the child and collect its piped output, signals sent to upt in the meantime are forwarded to it.
+///
+/// The previous dispositions are restored once it exits, so a signal at a prompt of upt still stops it.
+#[cfg(unix)]
+pub fn wait_child(child: Child) -> io::Result<Output> {
+    use std::sync::atomic::Ordering;
+
+    const SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+    // SAFETY: a zeroed sigaction is a valid value, it is overwritten by sigaction
+    let mut previous: [libc::sigaction; 3] = unsafe { std::mem::zeroed() };
+    for (sig, old) in SIGNALS.iter().zip(previous.iter_mut()) {
+        // SAFETY: the handler only uses atomics and calls kill, both are async-signal-safe
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = forward as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(*sig, &action, old);
+        }
+    }
+    CHILD.store(child.id() as i32, Ordering::SeqCst);
+    let output = child.wait_with_output();
+    CHILD.store(0, Ordering::SeqCst);
+    for (sig, old) in SIGNALS.iter().zip(previous.iter()) {
+        // SAFETY: the dispositions were returned by sigaction above
+        unsafe {
+            libc::sigaction(*sig, old, std::ptr::null_mut());
+        }
+    }
+    output
+}
+
+#[cfg(not(unix))]
+pub fn wait_child(child: Child) -> io::Result<Output> {
+    child.wait_with_output()
+}
+
+/// Exit code of a child that was interrupted, 128 + the signal like shells do.
+///
+/// The child is interrupted if it is killed by a signal or upt received one, see [`wait_child`].
+#[cfg(unix)]
+pub fn signal_code(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    let sig = match status.signal() {
+        Some(v) => v,
+        None => RECEIVED.load(std::sync::atomic::Ordering::SeqCst),
+    };
+    (sig > 0).then_some(128 + sig)
+}
+
+#[cfg(not(unix))]
+pub fn signal_code(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Exit code of a signal upt received since it started waiting for children, 128 + the signal.
+///
+/// A signal arriving between two commands has no child to reach, the next command must not run.
+#[cfg(unix)]
+pub(crate) fn pending_signal() -> Option<i32> {
+    let sig = RECEIVED.load(std::sync::atomic::Ordering::SeqCst);
+    (sig > 0).then_some(128 + sig)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn pending_signal() -> Option<i32> {
+    None
+}
+
+/// Pid of the child being waited for
+#[cfg(unix)]
+static CHILD: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+/// The last signal received, it is never reset since upt stops at the first one
+#[cfg(unix)]
+static RECEIVED: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[cfg(unix)]
+extern "C" fn forward(sig: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    RECEIVED.store(sig, std::sync::atomic::Ordering::SeqCst);
+    // Signals from the terminal, e.g. Ctrl-C, reach the whole process group, the child already has them
+    // SAFETY: the kernel passes a valid siginfo to SA_SIGINFO handlers
+    if unsafe { (*info).si_code } > 0 {
+        return;
+    }
+    let pid = CHILD.load(std::sync::atomic::Ordering::SeqCst);
+    if pid > 0 {
+        // SAFETY: kill is async-signal-safe
+        unsafe {
+            libc::kill(pid, sig);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::runner::{Capture, CommandRunner, LocalRunner};
+
+    #[test]
+    fn test_signal_code() {
+        let argv = ["sh", "-c", "kill -9 $$"].map(|v| v.to_string());
+        let output = LocalRunner.run(&argv, &[], None, Capture::Nothing).unwrap();
+        assert_eq!(signal_code(&output.status), Some(137));
+    }
+}
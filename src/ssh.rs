@@ -0,0 +1,84 @@
+use crate::runner::{query_line, spawn, wants_tty, which_through, Capture, CommandRunner};
+use crate::utils::{quote_posix, remote_os, OS_RELEASE};
+
+use std::io;
+use std::path::PathBuf;
+use std::process::Output;
+
+/// Runs the commands on another machine over ssh, upt does not need to be installed there
+#[derive(Debug)]
+pub struct SshRunner {
+    /// `[user@]host` as ssh takes it
+    destination: String,
+}
+
+impl SshRunner {
+    /// Run on `[user@]host`, ssh's own configuration applies
+    pub fn new(destination: &str) -> Self {
+        SshRunner {
+            destination: destination.to_string(),
+        }
+    }
+
+    /// The ssh command running the line it is given last remotely, with a tty if the tool may prompt
+    fn ssh_argv(&self, tty: bool) -> Vec<String> {
+        let tty = if tty { "-t" } else { "-T" };
+        // The destination is never taken as an option
+        ["ssh", tty, "--", &self.destination]
+            .map(|v| v.to_string())
+            .to_vec()
+    }
+}
+
+/// The argv as a line for the remote shell, the remote shell splits it again
+fn command_line(argv: &[String], env: &[(String, String)]) -> String {
+    let mut words = vec![];
+    if !env.is_empty() {
+        words.push("env".to_string());
+        words.extend(env.iter().map(|(k, v)| quote_posix(&format!("{k}={v}"))));
+    }
+    words.extend(argv.iter().map(|v| quote_posix(v)));
+    words.join(" ")
+}
+
+impl CommandRunner for SshRunner {
+    fn run(
+        &mut self,
+        argv: &[String],
+        env: &[(String, String)],
+        stdin: Option<&[u8]>,
+        capture: Capture,
+    ) -> io::Result<Output> {
+        let mut ssh = self.ssh_argv(wants_tty(stdin, capture));
+        ssh.push(command_line(argv, env));
+        spawn(&ssh, &[], stdin, capture)
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        self.which_all(&[program]).pop().flatten()
+    }
+
+    /// The programs are looked up over a single connection
+    fn which_all(&self, programs: &[&str]) -> Vec<Option<PathBuf>> {
+        which_through(&self.ssh_argv(false), programs)
+    }
+
+    fn detect_os(&self) -> Option<String> {
+        remote_os(&query_line(&self.ssh_argv(false), OS_RELEASE)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_argv() {
+        let ssh = SshRunner::new("root@box");
+        let argv = ["apt", "install", "vim", "foo bar"].map(|v| v.to_string());
+        let line = command_line(&argv, &[("LANG".to_string(), "C".to_string())]);
+        assert_eq!(line, "env LANG=C apt install vim 'foo bar'");
+        assert_eq!(ssh.ssh_argv(true), ["ssh", "-t", "--", "root@box"]);
+        assert_eq!(ssh.ssh_argv(false)[1], "-T");
+    }
+}
@@ -1,20 +1,131 @@
-/// General tasks that every vender provides
-#[derive(Debug, PartialEq)]
+use std::fmt;
+
+/// General tasks that every vender provides.
+///
+/// New tasks may be added in minor versions, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Task {
     /// install packages
-    Install { pkg: String, confirm: bool },
+    Install {
+        /// packages to install
+        pkgs: Vec<PackageSpec>,
+        /// proceed without asking
+        confirm: bool,
+    },
     /// remove packages
-    Remove { pkg: String, confirm: bool },
+    Remove {
+        /// packages to remove
+        pkgs: Vec<PackageSpec>,
+        /// proceed without asking
+        confirm: bool,
+    },
     /// upgrade packages
-    Upgrade { pkg: String, confirm: bool },
-    /// search for a package
-    Search { pkg: String },
-    /// show a package info
-    Info { pkg: String },
+    Upgrade {
+        /// packages to upgrade
+        pkgs: Vec<PackageSpec>,
+        /// proceed without asking
+        confirm: bool,
+    },
+    /// search for packages matching the query, its words are separate arguments
+    Search {
+        /// words to look for
+        query: String,
+    },
+    /// show the info of packages
+    Info {
+        /// packages to show
+        pkgs: Vec<PackageSpec>,
+    },
     /// sync packages index
     UpdateIndex,
     /// upgrade all outdated packages
-    UpgradeAll { confirm: bool },
+    UpgradeAll {
+        /// proceed without asking
+        confirm: bool,
+    },
     /// list all installed packages
     ListInstalled,
 }
+
+/// A package to install, remove or upgrade, optionally at a version
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct PackageSpec {
+    /// Name of the package
+    pub name: String,
+    /// Exact version, the tool's latest if `None`
+    pub version: Option<String>,
+}
+
+impl PackageSpec {
+    /// The package at any version
+    pub fn new(name: &str) -> Self {
+        PackageSpec {
+            name: name.to_string(),
+            version: None,
+        }
+    }
+
+    /// The package at exactly this version
+    pub fn with_version(name: &str, version: &str) -> Self {
+        PackageSpec {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+        }
+    }
+}
+
+/// `name` or `name=version`
+impl fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}={}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Specs of the packages in a command line, each word is a package name
+pub(crate) fn specs(words: &str) -> Vec<PackageSpec> {
+    words.split_whitespace().map(PackageSpec::new).collect()
+}
+
+impl Task {
+    /// Name of the task, the same as the action performing it, e.g. `update_index`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Task::Install { .. } => "install",
+            Task::Remove { .. } => "remove",
+            Task::Upgrade { .. } => "upgrade",
+            Task::Search { .. } => "search",
+            Task::Info { .. } => "info",
+            Task::UpdateIndex => "update_index",
+            Task::UpgradeAll { .. } => "upgrade_all",
+            Task::ListInstalled => "list_installed",
+        }
+    }
+
+    /// The packages or search terms of the task
+    pub(crate) fn packages(&self) -> Vec<String> {
+        match self {
+            Task::Install { pkgs, .. }
+            | Task::Remove { pkgs, .. }
+            | Task::Upgrade { pkgs, .. }
+            | Task::Info { pkgs } => pkgs.iter().map(|v| v.to_string()).collect(),
+            Task::Search { query } => query.split_whitespace().map(|v| v.to_string()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Whether the task skips confirmation, `None` if the task never asks
+    pub(crate) fn confirm(&self) -> Option<bool> {
+        match self {
+            Task::Install { confirm, .. }
+            | Task::Remove { confirm, .. }
+            | Task::Upgrade { confirm, .. }
+            | Task::UpgradeAll { confirm } => Some(*confirm),
+            _ => None,
+        }
+    }
+}
@@ -1,15 +1,13 @@
+use std::path::PathBuf;
 use which::which;
 
-pub fn find_tool(pairs: &[(&str, &str)]) -> Option<String> {
+/// Find the first tool whose binary exists, returns the tool and the path of the binary
+pub fn find_tool(pairs: &[(&str, &str)]) -> Option<(String, PathBuf)> {
     match pairs.len() {
         0 => None,
         1 => {
             let (tool, bin_name) = &pairs[0];
-            if which(bin_name).is_ok() {
-                Some(tool.to_string())
-            } else {
-                None
-            }
+            which(bin_name).ok().map(|path| (tool.to_string(), path))
         }
         _ => {
             let handles: Vec<_> = pairs
@@ -17,18 +15,12 @@ pub fn find_tool(pairs: &[(&str, &str)]) -> Option<String> {
                 .map(|(tool, bin_name)| {
                     let tool = tool.to_string();
                     let bin_name = bin_name.to_string();
-                    std::thread::spawn(move || {
-                        if which(&bin_name).is_ok() {
-                            Some(tool)
-                        } else {
-                            None
-                        }
-                    })
+                    std::thread::spawn(move || which(&bin_name).ok().map(|path| (tool, path)))
                 })
                 .collect();
             for handle in handles {
-                if let Ok(Some(tool)) = handle.join() {
-                    return Some(tool);
+                if let Ok(Some(v)) = handle.join() {
+                    return Some(v);
                 }
             }
             None
@@ -36,6 +28,7 @@ pub fn find_tool(pairs: &[(&str, &str)]) -> Option<String> {
     }
 }
 
+/// Id of the os upt runs on, e.g. `debian`
 #[cfg(target_os = "windows")]
 pub fn detect_os() -> Option<String> {
     if std::env::var("MSYSTEM").is_ok() {
@@ -53,21 +46,25 @@ pub fn detect_os() -> Option<String> {
     Some("windows".to_string())
 }
 
+/// Id of the os upt runs on, e.g. `debian`
 #[cfg(target_os = "macos")]
 pub fn detect_os() -> Option<String> {
     Some("macos".to_string())
 }
 
+/// Id of the os upt runs on, e.g. `debian`
 #[cfg(target_os = "android")]
 pub fn detect_os() -> Option<String> {
     Some("android".to_string())
 }
 
+/// Id of the os upt runs on, e.g. `debian`
 #[cfg(target_os = "haiku")]
 pub fn detect_os() -> Option<String> {
     Some("haiku".to_string())
 }
 
+/// Id of the os upt runs on, e.g. `debian`
 #[cfg(not(any(
     target_os = "windows",
     target_os = "macos",
@@ -76,7 +73,161 @@ pub fn detect_os() -> Option<String> {
 )))]
 pub fn detect_os() -> Option<String> {
     let release = std::fs::read_to_string("/etc/os-release").ok()?;
-    let id = release.lines().find(|l| l.starts_with("ID="))?;
-    let id = id[3..].trim_matches('"');
-    Some(id.to_string())
+    parse_os_release(&release)
+        .into_iter()
+        .find(|(k, _)| k == "ID")
+        .map(|(_, v)| v)
+}
+
+/// Parse the `KEY=value` fields of os-release
+pub(crate) fn parse_os_release(release: &str) -> Vec<(String, String)> {
+    release
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| {
+            let v = v.trim();
+            let v = v
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| v.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(v);
+            (k.trim().to_string(), v.to_string())
+        })
+        .collect()
+}
+
+/// Prints the os of another machine, see [`remote_os`]
+pub(crate) const OS_RELEASE: &str = "cat /etc/os-release 2>/dev/null || uname -s";
+
+/// The os from the output of [`OS_RELEASE`]
+pub(crate) fn remote_os(output: &str) -> Option<String> {
+    if let Some((_, id)) = parse_os_release(output)
+        .into_iter()
+        .find(|(k, _)| k == "ID")
+    {
+        return Some(id);
+    }
+    match output.trim() {
+        "" => None,
+        "Darwin" => Some("macos".to_string()),
+        v => Some(v.to_lowercase()),
+    }
+}
+
+/// Prints the path of each program on its own line, an empty line if it is not installed
+pub(crate) fn which_line(programs: &[&str]) -> String {
+    let words: Vec<String> = programs.iter().map(|v| quote_posix(v)).collect();
+    format!(
+        "for p in {}; do command -v \"$p\" || echo; done",
+        words.join(" ")
+    )
+}
+
+/// The paths from the output of [`which_line`], all unknown if it failed
+pub(crate) fn parse_which(output: Option<String>, count: usize) -> Vec<Option<PathBuf>> {
+    let mut lines = output.as_deref().unwrap_or_default().lines();
+    (0..count)
+        .map(|_| {
+            let path = lines.next().unwrap_or_default().trim();
+            (!path.is_empty()).then(|| PathBuf::from(path))
+        })
+        .collect()
+}
+
+/// Join the command into a line that runs exactly the same argv when pasted into the shell.
+///
+/// PowerShell quoting is used on windows unless running in msys2.
+pub(crate) fn shell_join<T: AsRef<str>>(argv: &[T]) -> String {
+    let powershell = cfg!(target_os = "windows") && std::env::var("MSYSTEM").is_err();
+    let words: Vec<String> = argv
+        .iter()
+        .map(|v| {
+            if powershell {
+                quote_powershell(v.as_ref())
+            } else {
+                quote_posix(v.as_ref())
+            }
+        })
+        .collect();
+    words.join(" ")
+}
+
+fn is_plain(v: &str) -> bool {
+    !v.is_empty()
+        && v.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c))
+}
+
+pub(crate) fn quote_posix(v: &str) -> String {
+    if is_plain(v) {
+        return v.to_string();
+    }
+    format!("'{}'", v.replace('\'', r"'\''"))
+}
+
+fn quote_powershell(v: &str) -> String {
+    // `@` starts a splat and `,` builds an array at the beginning of a word in PowerShell
+    if is_plain(v) && !v.starts_with(['@', ',']) {
+        return v.to_string();
+    }
+    format!("'{}'", v.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_os_release() {
+        let release = r#"PRETTY_NAME="Debian GNU/Linux 12 (bookworm)"
+# comment
+ID=debian
+VERSION_ID='12'
+"#;
+        assert_eq!(
+            parse_os_release(release),
+            vec![
+                (
+                    "PRETTY_NAME".to_string(),
+                    "Debian GNU/Linux 12 (bookworm)".to_string()
+                ),
+                ("ID".to_string(), "debian".to_string()),
+                ("VERSION_ID".to_string(), "12".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remote_os() {
+        assert_eq!(remote_os("NAME=\"Arch Linux\"\nID=arch\n").unwrap(), "arch");
+        assert_eq!(remote_os("Darwin\n").unwrap(), "macos");
+        assert_eq!(remote_os(""), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_which_line() {
+        let line = which_line(&["sh", "no-such-upt-tool", "sh"]);
+        let output = crate::runner::query(&["sh", "-c", &line].map(|v| v.to_string()));
+        let paths = parse_which(output, 3);
+        assert!(paths[0].is_some());
+        assert_eq!(paths[1], None);
+        assert_eq!(paths[2], paths[0]);
+        assert_eq!(parse_which(None, 2), [None, None]);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote_posix("vim"), "vim");
+        assert_eq!(quote_posix("python3.11-dev"), "python3.11-dev");
+        assert_eq!(quote_posix("*"), "'*'");
+        assert_eq!(quote_posix("foo>=1.2"), "'foo>=1.2'");
+        assert_eq!(quote_posix("a b"), "'a b'");
+        assert_eq!(quote_posix("it's"), r"'it'\''s'");
+        assert_eq!(quote_posix(""), "''");
+        assert_eq!(quote_powershell("it's"), "'it''s'");
+        assert_eq!(quote_powershell("@types/node"), "'@types/node'");
+        assert_eq!(quote_powershell("$env"), "'$env'");
+    }
 }
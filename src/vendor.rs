@@ -1,6 +1,16 @@
 use crate::action::Action;
 use crate::error::UptError;
-use crate::task::Task;
+use crate::exit::ExitCode;
+use crate::format::{json_array, json_lines, json_str, render_table, Format};
+use crate::info::PackageInfo;
+use crate::installed::InstalledPackage;
+use crate::plan::{OnFailure, Plan, Step};
+use crate::runner::{CommandRunner, LocalRunner};
+use crate::search::SearchResult;
+use crate::task::{specs, PackageSpec, Task};
+use crate::utils::find_tool;
+
+use std::path::PathBuf;
 
 os_vendors!(
   "windows" => "scoop", "choco", "winget";
@@ -103,14 +113,14 @@ vendors![
     },
     {
         name: "apt",
-        confirm: "-y/--yes",
-        install: "apt install $",
-        remove: "apt remove $",
-        upgrade: "apt install --only-upgrade $",
-        search: "apt search $",
-        info: "apt show $",
-        update_index: "apt update",
-        upgrade_all: "apt upgrade",
+        confirm: "-y/--yes/--assume-yes",
+        install: "apt/apt-get install $",
+        remove: "apt/apt-get remove $",
+        upgrade: "apt/apt-get install --only-upgrade $",
+        search: "apt/apt-cache search $",
+        info: "apt/apt-cache show $",
+        update_index: "apt/apt-get update",
+        upgrade_all: "apt/apt-get upgrade",
         list_installed: "apt list -i/--installed",
     },
     {
@@ -123,7 +133,7 @@ vendors![
         info: "brew info $",
         update_index: "brew update",
         upgrade_all: "brew upgrade",
-        list_installed: "brew list",
+        list_installed: "brew list --versions",
     },
     {
         name: "cards",
@@ -257,6 +267,18 @@ vendors![
         upgrade_all: "pacman -S -y -u",
         list_installed: "pacman -Q",
     },
+    {
+        name: "paru",
+        confirm: "--noconfirm",
+        install: "paru -S $",
+        remove: "paru -R -s $",
+        upgrade: "paru -S $",
+        search: "paru -S -s $",
+        info: "paru -S -i $",
+        update_index: "paru -S -y",
+        upgrade_all: "paru -S -y -u",
+        list_installed: "paru -Q",
+    },
     {
         name: "pkg",
         confirm: "-y/--yes",
@@ -293,6 +315,18 @@ vendors![
         upgrade_all: "pkgman update",
         list_installed: "pkgman search -i/--installed-only -a/--all",
     },
+    {
+        name: "port",
+        confirm: "",
+        install: "port install $",
+        remove: "port uninstall $",
+        upgrade: "port upgrade $",
+        search: "port search $",
+        info: "port info $",
+        update_index: "port selfupdate",
+        upgrade_all: "port upgrade outdated",
+        list_installed: "port installed",
+    },
     {
         name: "prt-get",
         confirm: "",
@@ -415,6 +449,381 @@ vendors![
     },
 ];
 
+/// Names of the actions, in the order of `Vendor::actions`
+pub(crate) const ACTIONS: [&str; 8] = [
+    "install",
+    "remove",
+    "upgrade",
+    "search",
+    "info",
+    "update_index",
+    "upgrade_all",
+    "list_installed",
+];
+
+/// Packages providing the binaries of actions, for the binaries that are not the tool's own
+pub(crate) const PROVIDERS: [(&str, &str); 11] = [
+    ("ls", "coreutils"),
+    ("nix-channel", "nix"),
+    ("ports", "ports"),
+    ("qlist", "app-portage/portage-utils"),
+    ("rpm", "rpm"),
+    ("urpme", "urpmi"),
+    ("urpmi", "urpmi"),
+    ("urpmi.update", "urpmi"),
+    ("urpmq", "urpmi"),
+    ("xbps-query", "xbps"),
+    ("xbps-remove", "xbps"),
+];
+
+/// Tools whose upgrade_all only upgrades to the versions of the last index sync, upt syncs it first
+const SYNC_BEFORE_UPGRADE: [&str; 1] = ["nix-env"];
+
+/// Commands listing the packages installed explicitly, not as dependencies
+const EXPLICIT_QUERIES: [(&str, &[&str]); 11] = [
+    ("apk", &["cat", "/etc/apk/world"]),
+    ("apt", &["apt-mark", "showmanual"]),
+    ("brew", &["brew", "leaves"]),
+    (
+        "dnf",
+        &[
+            "dnf",
+            "repoquery",
+            "--userinstalled",
+            "--queryformat",
+            "%{name}",
+        ],
+    ),
+    ("nala", &["apt-mark", "showmanual"]),
+    ("pacman", &["pacman", "-Qeq"]),
+    ("paru", &["pacman", "-Qeq"]),
+    ("pkg", &["pkg", "query", "-e", "%a == 0", "%n"]),
+    ("pkg(termux)", &["apt-mark", "showmanual"]),
+    ("xbps", &["xbps-query", "-m"]),
+    ("yay", &["pacman", "-Qeq"]),
+];
+
+/// Commands listing the packages the os needs, which are never pruned
+const ESSENTIAL_QUERIES: [(&str, &[&str]); 5] = [
+    (
+        "apt",
+        &[
+            "dpkg-query",
+            "-W",
+            "-f",
+            "${Package} ${Essential} ${Priority}\n",
+        ],
+    ),
+    (
+        "dnf",
+        &[
+            "sh",
+            "-c",
+            "cat /etc/dnf/protected.d/*.conf 2>/dev/null; true",
+        ],
+    ),
+    (
+        "nala",
+        &[
+            "dpkg-query",
+            "-W",
+            "-f",
+            "${Package} ${Essential} ${Priority}\n",
+        ],
+    ),
+    (
+        "pkg(termux)",
+        &[
+            "dpkg-query",
+            "-W",
+            "-f",
+            "${Package} ${Essential} ${Priority}\n",
+        ],
+    ),
+    (
+        "yum",
+        &[
+            "sh",
+            "-c",
+            "cat /etc/yum/protected.d/*.conf 2>/dev/null; true",
+        ],
+    ),
+];
+
+/// Each word is a separate argument
+fn words(v: &str) -> Vec<String> {
+    v.split_whitespace().map(|v| v.to_string()).collect()
+}
+
+/// How a tool names a package at a version
+const VERSION_SYNTAX: [(&str, &str); 12] = [
+    ("apk", "{name}={version}"),
+    ("apt", "{name}={version}"),
+    ("brew", "{name}@{version}"),
+    ("dnf", "{name}-{version}"),
+    ("emerge", "={name}-{version}"),
+    ("guix", "{name}@{version}"),
+    ("nala", "{name}={version}"),
+    ("pkg(termux)", "{name}={version}"),
+    ("scoop", "{name}@{version}"),
+    ("urpm", "{name}-{version}"),
+    ("yum", "{name}-{version}"),
+    ("zypper", "{name}={version}"),
+];
+
+/// Native exit codes of a tool's action and what they mean to upt, `*` matches any action.
+///
+/// Other non-zero codes are failures.
+pub(crate) const EXIT_CODES: [(&str, &str, i32, ExitCode); 28] = [
+    ("apt", "info", 100, ExitCode::PackageNotFound),
+    ("brew", "search", 1, ExitCode::PackageNotFound),
+    ("brew", "info", 1, ExitCode::PackageNotFound),
+    ("choco", "*", 2, ExitCode::NothingToDo),
+    ("choco", "*", 1641, ExitCode::Success),
+    ("choco", "*", 3010, ExitCode::Success),
+    ("dnf", "update_index", 100, ExitCode::UpdatesAvailable),
+    ("dnf", "info", 1, ExitCode::PackageNotFound),
+    ("flatpak", "info", 1, ExitCode::PackageNotFound),
+    ("pacman", "search", 1, ExitCode::PackageNotFound),
+    ("pacman", "info", 1, ExitCode::PackageNotFound),
+    ("paru", "search", 1, ExitCode::PackageNotFound),
+    ("paru", "info", 1, ExitCode::PackageNotFound),
+    ("snap", "info", 1, ExitCode::PackageNotFound),
+    (
+        "winget",
+        "*",
+        0x8A150014_u32 as i32,
+        ExitCode::PackageNotFound,
+    ),
+    ("winget", "*", 0x8A15002B_u32 as i32, ExitCode::NothingToDo),
+    ("winget", "*", 0x8A150061_u32 as i32, ExitCode::NothingToDo),
+    ("xbps", "install", 2, ExitCode::PackageNotFound),
+    ("xbps", "install", 17, ExitCode::NothingToDo),
+    ("yay", "search", 1, ExitCode::PackageNotFound),
+    ("yay", "info", 1, ExitCode::PackageNotFound),
+    ("yum", "update_index", 100, ExitCode::UpdatesAvailable),
+    ("yum", "info", 1, ExitCode::PackageNotFound),
+    ("zypper", "*", 5, ExitCode::PermissionDenied),
+    ("zypper", "*", 100, ExitCode::UpdatesAvailable),
+    ("zypper", "*", 101, ExitCode::UpdatesAvailable),
+    ("zypper", "*", 102, ExitCode::Success),
+    ("zypper", "*", 104, ExitCode::PackageNotFound),
+];
+
+/// Options making an action print json, which upt parses rather than the text when a format is given
+const JSON_OPTIONS: [(&str, &str, &str); 1] = [("brew", "info", "--json=v2")];
+
+/// Messages a tool prints on stderr when it fails because it does not run as root
+const DENIED_MESSAGES: [(&str, &str); 11] = [
+    ("apk", "Permission denied"),
+    ("apt", "are you root?"),
+    ("dnf", "superuser privileges"),
+    ("emerge", "root access required"),
+    ("nala", "needs root"),
+    ("pacman", "unless you are root"),
+    ("pkg", "Insufficient privileges"),
+    ("snap", "access denied"),
+    ("urpm", "Only superuser"),
+    ("xbps", "Permission denied"),
+    ("yum", "need to be root"),
+];
+
+/// Commands that only upt itself provides
+const UPT_COMMANDS: [(&str, &str); 8] = [
+    (
+        "upt apply [-f <file>] [--prune] [-y] [--refresh] [--plan]",
+        "Install the packages of the manifest that are missing",
+    ),
+    (
+        "upt export [--explicit|--all] [--format upt|json|txt]",
+        "Print the explicitly installed packages as a manifest",
+    ),
+    (
+        "upt translate --to <tool>/--all <command>",
+        "Print the commands other tools run for the task",
+    ),
+    (
+        "upt --list-tools [--format <format>]",
+        "List the commands of all supported tools",
+    ),
+    (
+        "upt --list-os [--format <format>]",
+        "List the tools used on each os",
+    ),
+    (
+        "upt completions <shell> [--as <tool>]",
+        "Generate the shell completion script",
+    ),
+    ("upt --man [--as <tool>]", "Generate the man page"),
+    ("upt doctor", "Diagnose how the tool is picked"),
+];
+
+/// Detect the package management tool of the os
+pub fn detect_vendor(os: &str) -> Result<Vendor, UptError> {
+    let (tool, _) = detect_tool(os)?;
+    init_vendor(&tool)
+}
+
+/// Candidate tools of the os in order of preference, paired with their binary names
+pub(crate) fn os_tools(os: &str) -> Vec<(&'static str, &'static str)> {
+    let table = os_table();
+    let tools = match table.iter().find(|(v, _)| *v == os) {
+        Some((_, tools)) => tools,
+        None => &table[table.len() - 1].1,
+    };
+    tools
+        .iter()
+        .filter_map(|tool| which_cmd(tool).map(|bin_name| (*tool, bin_name)))
+        .collect()
+}
+
+/// Find the tool of the os, returns its name and the path of its binary
+pub(crate) fn detect_tool(os: &str) -> Result<(String, PathBuf), UptError> {
+    find_tool(&os_tools(os)).ok_or(UptError::NoDetectVendor)
+}
+
+/// Find the tool of the os where the runner runs commands
+pub(crate) fn detect_tool_with(
+    runner: &dyn CommandRunner,
+    os: &str,
+) -> Result<(String, PathBuf), UptError> {
+    find_tool_with(runner, &os_tools(os)).ok_or(UptError::NoDetectVendor)
+}
+
+/// The first tool whose binary the runner finds, all binaries are probed at once
+fn find_tool_with(runner: &dyn CommandRunner, pairs: &[(&str, &str)]) -> Option<(String, PathBuf)> {
+    let bin_names: Vec<&str> = pairs.iter().map(|(_, bin_name)| *bin_name).collect();
+    runner
+        .which_all(&bin_names)
+        .into_iter()
+        .zip(pairs)
+        .find_map(|(path, (tool, _))| path.map(|path| (tool.to_string(), path)))
+}
+
+/// Pick the first available tool from a comma-separated preference list, e.g. `paru,yay,pacman`
+///
+/// Unsupported tools are skipped like missing ones, they are named if no tool is available.
+pub fn select_vendor(tools: &str) -> Result<Vendor, UptError> {
+    match find_tool(&tool_pairs(tools)) {
+        Some((tool, _)) => init_vendor(&tool),
+        None => Err(no_available_vendor(tools)),
+    }
+}
+
+/// Pick the first tool of the preference list available where the runner runs commands
+pub fn select_vendor_with(runner: &dyn CommandRunner, tools: &str) -> Result<Vendor, UptError> {
+    match find_tool_with(runner, &tool_pairs(tools)) {
+        Some((tool, _)) => init_vendor(&tool),
+        None => Err(no_available_vendor(tools)),
+    }
+}
+
+/// The tools of a preference list, without empty entries
+fn tool_names(tools: &str) -> impl Iterator<Item = &str> {
+    tools.split(',').map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+/// The supported tools of a preference list paired with their binary names
+fn tool_pairs(tools: &str) -> Vec<(&str, &'static str)> {
+    tool_names(tools)
+        .filter_map(|tool| which_cmd(tool).map(|bin_name| (tool, bin_name)))
+        .collect()
+}
+
+/// Every tool of the list, the unsupported ones marked
+fn no_available_vendor(tools: &str) -> UptError {
+    UptError::NoAvailableVendor(
+        tool_names(tools)
+            .map(|tool| match which_cmd(tool) {
+                Some(_) => tool.to_string(),
+                None => format!("{tool} (not supported)"),
+            })
+            .collect(),
+    )
+}
+
+/// Find the vendor whose syntax the command `bin` speaks, e.g. `apt-get` => apt
+pub fn persona_vendor(bin: &str) -> Result<Vendor, UptError> {
+    if let Ok(vendor) = init_vendor(bin) {
+        return Ok(vendor);
+    }
+    support_tools()
+        .into_iter()
+        .filter_map(|tool| init_vendor(tool).ok())
+        .find(|vendor| vendor.personas().contains(&bin))
+        .ok_or_else(|| UptError::NoVendor(bin.to_string()))
+}
+
+/// Render the commands of every supported tool, in markdown, json or csv
+pub fn list_tools(format: Format) -> String {
+    let columns = [
+        ("tool", "Tool"),
+        ("install", "Install"),
+        ("remove", "Uninstall"),
+        ("upgrade", "Upgrade"),
+        ("search", "Search"),
+        ("info", "Info"),
+        ("update_index", "Update Index"),
+        ("upgrade_all", "Upgrade All"),
+        ("list_installed", "List Installed"),
+    ];
+    let rows: Vec<Vec<Option<String>>> = support_tools()
+        .into_iter()
+        .filter_map(|tool| init_vendor(tool).ok())
+        .map(|vendor| {
+            let mut row = vec![Some(vendor.name.clone())];
+            row.extend(vendor.actions().iter().map(|action| action.help()));
+            row
+        })
+        .collect();
+    render_table(&columns, &rows, format)
+}
+
+/// Render the tools used on each os, as a table, in markdown, json or csv
+pub fn list_os(format: Format) -> String {
+    let table = os_table();
+    match format {
+        Format::Json => json_lines(
+            table
+                .iter()
+                .map(|(os, tools)| {
+                    format!(
+                        "  {{\"os\":{},\"tools\":{}}}",
+                        json_str(os),
+                        json_array(tools)
+                    )
+                })
+                .collect(),
+        ),
+        Format::Csv => {
+            let rows: Vec<Vec<Option<String>>> = table
+                .iter()
+                .map(|(os, tools)| vec![Some(os.to_string()), Some(tools.join(","))])
+                .collect();
+            render_table(&[("os", "OS"), ("tools", "Tools")], &rows, format)
+        }
+        Format::Table | Format::Markdown => {
+            let rows: Vec<Vec<Option<String>>> = os_groups()
+                .iter()
+                .map(|(oses, tools)| vec![Some(oses.join(", ")), Some(tools.join(", "))])
+                .collect();
+            render_table(&[("os", "OS"), ("tools", "Tools")], &rows, format)
+        }
+    }
+}
+
+/// Group adjacent oses of the os table that use the same tools
+pub(crate) fn os_groups() -> Vec<(Vec<&'static str>, Vec<&'static str>)> {
+    let mut groups: Vec<(Vec<&str>, Vec<&str>)> = vec![];
+    for (os, tools) in os_table() {
+        match groups.last_mut() {
+            Some((oses, v)) if *v == tools => oses.push(os),
+            _ => groups.push((vec![os], tools)),
+        }
+    }
+    groups
+}
+
 /// Represent a kind of package management tool. e.g. apt, pacman, yum...
 #[derive(Debug, Clone, PartialEq)]
 pub struct Vendor {
@@ -431,29 +840,77 @@ pub struct Vendor {
 }
 
 impl Vendor {
+    /// Name of the tool, e.g. `apt`
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Commands speaking the vendor's syntax: the install command with its aliases such as apt-get for apt,
+    /// and the commands named after the vendor such as xbps-remove.
+    ///
+    /// Helpers of other packages such as `rpm` for urpm or `ls` for slackpkg are not personas.
+    pub(crate) fn personas(&self) -> Vec<&str> {
+        let primary = self.install.cmds().first();
+        let mut personas: Vec<&str> = vec![];
+        for action in self.actions() {
+            let grouped = primary.is_some_and(|v| action.cmds().contains(v));
+            for cmd in action.cmds() {
+                if (grouped || cmd.starts_with(&self.name)) && !personas.contains(&cmd.as_str()) {
+                    personas.push(cmd);
+                }
+            }
+        }
+        personas
+    }
+
+    /// Binaries the actions run, e.g. qlist for emerge's list_installed
+    pub(crate) fn binaries(&self) -> Vec<&str> {
+        let mut binaries: Vec<&str> = vec![];
+        for action in self.actions() {
+            if let Some(bin_name) = action.cmds().first() {
+                if !binaries.contains(&bin_name.as_str()) {
+                    binaries.push(bin_name);
+                }
+            }
+        }
+        binaries
+    }
+
+    pub(crate) fn actions(&self) -> [&Action; 8] {
+        [
+            &self.install,
+            &self.remove,
+            &self.upgrade,
+            &self.search,
+            &self.info,
+            &self.update_index,
+            &self.upgrade_all,
+            &self.list_installed,
+        ]
+    }
+
     /// Parse command line, figure out the task to perform
     pub fn parse(&self, args: &[String], upt_tool: &str) -> Result<Task, UptError> {
         if self.is_help(args) {
             return Err(UptError::DisplayHelp(self.help(upt_tool)));
         }
         if let Some((Some(pkg), yes)) = self.install.parse(args, &self.confirm) {
-            return Ok(Task::Install { pkg, confirm: yes });
+            let pkgs = specs(&pkg);
+            return Ok(Task::Install { pkgs, confirm: yes });
         }
         if let Some((Some(pkg), yes)) = self.remove.parse(args, &self.confirm) {
-            return Ok(Task::Remove { pkg, confirm: yes });
+            let pkgs = specs(&pkg);
+            return Ok(Task::Remove { pkgs, confirm: yes });
         }
         if let Some((Some(pkg), yes)) = self.upgrade.parse(args, &self.confirm) {
-            return Ok(Task::Upgrade { pkg, confirm: yes });
+            let pkgs = specs(&pkg);
+            return Ok(Task::Upgrade { pkgs, confirm: yes });
         }
         if let Some((Some(pkg), _)) = self.search.parse(args, "") {
-            return Ok(Task::Search { pkg });
+            return Ok(Task::Search { query: pkg });
         }
         if let Some((Some(pkg), _)) = self.info.parse(args, "") {
-            return Ok(Task::Info { pkg });
+            return Ok(Task::Info { pkgs: specs(&pkg) });
         }
         if self.update_index.parse(args, "").is_some() {
             return Ok(Task::UpdateIndex);
@@ -467,21 +924,201 @@ impl Vendor {
         Err(UptError::InvalidArgs(self.help(upt_tool)))
     }
 
-    /// Convert the task to command line, which invokes the os's package management tool.
-    pub fn eval(&self, task: &Task) -> Result<Vec<String>, UptError> {
+    /// Locate the binary of a command, hints the package providing it if missing
+    pub fn locate(&self, bin_name: &str) -> Result<PathBuf, UptError> {
+        self.locate_with(&LocalRunner, bin_name)
+    }
+
+    /// Locate the binary where the runner runs commands
+    pub fn locate_with(
+        &self,
+        runner: &dyn CommandRunner,
+        bin_name: &str,
+    ) -> Result<PathBuf, UptError> {
+        runner.which(bin_name).ok_or_else(|| {
+            let package = PROVIDERS
+                .iter()
+                .find(|(v, _)| *v == bin_name && self.binaries().contains(v))
+                .map(|(_, package)| package.to_string());
+            UptError::NoCommand(bin_name.to_string(), package)
+        })
+    }
+
+    /// Translate the native exit code of an action into upt's exit code
+    pub fn exit_code(&self, action: &str, native: i32) -> ExitCode {
+        if native == 0 {
+            return ExitCode::Success;
+        }
+        EXIT_CODES
+            .iter()
+            .find(|(tool, v, code, _)| {
+                *tool == self.name && (*v == action || *v == "*") && *code == native
+            })
+            .map(|(_, _, _, v)| *v)
+            .unwrap_or(ExitCode::Failure)
+    }
+
+    /// The command listing the packages installed explicitly, None if the tool has none
+    pub fn explicit_query(&self) -> Option<Vec<String>> {
+        EXPLICIT_QUERIES
+            .iter()
+            .find(|(tool, _)| *tool == self.name)
+            .map(|(_, argv)| argv.iter().map(|v| v.to_string()).collect())
+    }
+
+    /// Parse the output of the explicit query into package names
+    pub fn parse_explicit(&self, output: &str) -> Vec<String> {
+        crate::installed::parse_explicit(&self.name, output)
+    }
+
+    /// The command listing the packages the os needs, None if the tool has none
+    pub fn essential_query(&self) -> Option<Vec<String>> {
+        ESSENTIAL_QUERIES
+            .iter()
+            .find(|(tool, _)| *tool == self.name)
+            .map(|(_, argv)| argv.iter().map(|v| v.to_string()).collect())
+    }
+
+    /// Parse the output of the essential query into package names
+    pub fn parse_essential(&self, output: &str) -> Vec<String> {
+        crate::installed::parse_essential(&self.name, output)
+    }
+
+    /// Parse the output of the list_installed action
+    pub fn parse_installed(&self, output: &str) -> Vec<InstalledPackage> {
+        crate::installed::parse_installed(&self.name, output)
+    }
+
+    /// Parse the output of the search action
+    pub fn parse_search(&self, output: &str) -> Vec<SearchResult> {
+        crate::search::parse_search(&self.name, output)
+    }
+
+    /// Parse the output of the info action
+    pub fn parse_info(&self, output: &str) -> Vec<PackageInfo> {
+        crate::info::parse_info(&self.name, output)
+    }
+
+    /// Whether a failure was caused by missing privileges, from the stderr of the command
+    pub(crate) fn denied(&self, stderr: &[u8]) -> bool {
+        let stderr = String::from_utf8_lossy(stderr);
+        DENIED_MESSAGES
+            .iter()
+            .any(|(tool, message)| *tool == self.name && stderr.contains(message))
+    }
+
+    /// Whether the action can fail for missing privileges with a message [`Self::denied`] knows
+    pub(crate) fn may_deny(&self, action: &str) -> bool {
+        !matches!(action, "search" | "info" | "list_installed")
+            && DENIED_MESSAGES.iter().any(|(tool, _)| *tool == self.name)
+    }
+
+    /// Whether the native exit codes of the action need translating into upt's.
+    ///
+    /// Only the codes upt maps count, an action without any may keep its native codes.
+    /// A search or info finding nothing is told from its output.
+    pub fn translates_exit_codes(&self, action: &str) -> bool {
+        matches!(action, "search" | "info")
+            || EXIT_CODES
+                .iter()
+                .any(|(tool, v, _, _)| *tool == self.name && (*v == action || *v == "*"))
+    }
+
+    /// Plan the commands performing the task, the os of the plan is left empty.
+    ///
+    /// ```
+    /// use upt::{init_vendor, PackageSpec, Task};
+    ///
+    /// let apt = init_vendor("apt").unwrap();
+    /// let task = Task::Install {
+    ///     pkgs: vec![PackageSpec::new("vim"), PackageSpec::with_version("jq", "1.6-2.1")],
+    ///     confirm: true,
+    /// };
+    /// assert_eq!(apt.eval(&task).unwrap().steps[0].argv, ["apt", "install", "vim", "jq=1.6-2.1", "-y"]);
+    ///
+    /// // nix-env only sees the updates of the synced channels
+    /// let nix = init_vendor("nix-env").unwrap();
+    /// let plan = nix.eval(&Task::UpgradeAll { confirm: false }).unwrap();
+    /// assert_eq!(plan.to_string(), "nix-channel --update\nnix-env -u");
+    /// ```
+    pub fn eval(&self, task: &Task) -> Result<Plan, UptError> {
+        let mut steps = vec![Step {
+            action: task.kind(),
+            argv: self.argv(task)?,
+            on_failure: OnFailure::Abort,
+            path: None,
+        }];
+        if matches!(task, Task::UpgradeAll { .. }) && SYNC_BEFORE_UPGRADE.contains(&self.name()) {
+            let step = Step {
+                action: "update_index",
+                argv: self.argv(&Task::UpdateIndex)?,
+                on_failure: OnFailure::Abort,
+                path: None,
+            };
+            steps.insert(0, step);
+        }
+        Ok(Plan {
+            os: String::new(),
+            tool: self.name.clone(),
+            task: task.clone(),
+            steps,
+        })
+    }
+
+    /// The task's command printing json, None if the tool only prints text for it
+    pub(crate) fn json_argv(&self, task: &Task) -> Option<Vec<String>> {
+        let (_, _, option) = JSON_OPTIONS
+            .iter()
+            .find(|(tool, action, _)| *tool == self.name && *action == task.kind())?;
+        let (_, action) = ACTIONS
+            .iter()
+            .zip(self.actions())
+            .find(|(v, _)| **v == task.kind())?;
+        let mut argv = self.argv(task).ok()?;
+        // After the command and its options, before the packages
+        argv.insert(action.to_cmd(&[], "")?.len(), option.to_string());
+        Some(argv)
+    }
+
+    /// Convert the task to the command line of its action, the first element is the program
+    pub(crate) fn argv(&self, task: &Task) -> Result<Vec<String>, UptError> {
         let cmd = match task {
-            Task::Install { pkg, confirm: yes } => self.install.to_cmd(pkg, self.yes_str(yes)),
-            Task::Remove { pkg, confirm: yes } => self.remove.to_cmd(pkg, self.yes_str(yes)),
-            Task::Upgrade { pkg, confirm: yes } => self.upgrade.to_cmd(pkg, self.yes_str(yes)),
-            Task::Search { pkg } => self.search.to_cmd(pkg, ""),
-            Task::Info { pkg } => self.info.to_cmd(pkg, ""),
-            Task::UpdateIndex => self.update_index.to_cmd("", ""),
-            Task::UpgradeAll { confirm: yes } => self.upgrade_all.to_cmd("", self.yes_str(yes)),
-            Task::ListInstalled => self.list_installed.to_cmd("", ""),
+            Task::Install { pkgs, confirm: yes } => self
+                .install
+                .to_cmd(&self.spec_args(pkgs)?, self.yes_str(yes)),
+            Task::Remove { pkgs, confirm: yes } => self
+                .remove
+                .to_cmd(&self.spec_args(pkgs)?, self.yes_str(yes)),
+            Task::Upgrade { pkgs, confirm: yes } => self
+                .upgrade
+                .to_cmd(&self.spec_args(pkgs)?, self.yes_str(yes)),
+            Task::Search { query } => self.search.to_cmd(&words(query), ""),
+            Task::Info { pkgs } => self.info.to_cmd(&self.spec_args(pkgs)?, ""),
+            Task::UpdateIndex => self.update_index.to_cmd(&[], ""),
+            Task::UpgradeAll { confirm: yes } => self.upgrade_all.to_cmd(&[], self.yes_str(yes)),
+            Task::ListInstalled => self.list_installed.to_cmd(&[], ""),
         };
         cmd.ok_or(UptError::InvalidTask)
     }
 
+    /// Arguments naming the packages, versions are written in the tool's syntax
+    fn spec_args(&self, pkgs: &[PackageSpec]) -> Result<Vec<String>, UptError> {
+        pkgs.iter()
+            .map(|spec| match &spec.version {
+                None => Ok(spec.name.clone()),
+                Some(version) => {
+                    let (_, template) = VERSION_SYNTAX
+                        .iter()
+                        .find(|(tool, _)| *tool == self.name)
+                        .ok_or_else(|| UptError::NoVersion(self.name.clone()))?;
+                    Ok(template
+                        .replace("{name}", &spec.name)
+                        .replace("{version}", version))
+                }
+            })
+            .collect()
+    }
+
     fn yes_str(&self, yes: &bool) -> &str {
         if !*yes || self.confirm.is_empty() {
             return "";
@@ -504,21 +1141,7 @@ impl Vendor {
     fn help(&self, upt_tool: &str) -> String {
         let mut lines: Vec<String> = Vec::new();
         lines.push(String::from("Usage: "));
-        let helps = vec![
-            (self.install.help(), "Install packages"),
-            (self.remove.help(), "Remove packages"),
-            (self.upgrade.help(), "Upgrade packages"),
-            (self.search.help(), "Search for packages"),
-            (self.info.help(), "Show package details"),
-            (self.update_index.help(), "Update package indexes"),
-            (self.upgrade_all.help(), "Upgrade all packages"),
-            (self.list_installed.help(), "List all installed packages"),
-        ];
-        let helps: Vec<(&String, &str)> = helps
-            .iter()
-            .filter(|(v, _)| v.is_some())
-            .map(|(v, d)| (v.as_ref().unwrap(), *d))
-            .collect();
+        let helps = self.usages();
         let width = helps.iter().map(|(v, _)| v.len()).max().unwrap() + 6;
         for (cmd, description) in &helps {
             lines.push(format!("  {:<width$} {}", cmd, description, width = width));
@@ -531,18 +1154,44 @@ impl Vendor {
         }
         lines.join("\n")
     }
+
+    /// Usage of each command paired with its description
+    pub(crate) fn usages(&self) -> Vec<(String, &'static str)> {
+        let helps = [
+            (self.install.help(), "Install packages"),
+            (self.remove.help(), "Remove packages"),
+            (self.upgrade.help(), "Upgrade packages"),
+            (self.search.help(), "Search for packages"),
+            (self.info.help(), "Show package details"),
+            (self.update_index.help(), "Update package indexes"),
+            (self.upgrade_all.help(), "Upgrade all packages"),
+            (self.list_installed.help(), "List all installed packages"),
+        ];
+        let mut helps: Vec<(String, &'static str)> = helps
+            .into_iter()
+            .filter_map(|(v, d)| v.map(|v| (v, d)))
+            .collect();
+        if self.name == "upt" {
+            helps.extend(UPT_COMMANDS.iter().map(|(v, d)| (v.to_string(), *d)));
+        }
+        helps
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runner::RecordingRunner;
 
     macro_rules! check_parse {
         ($vendor:expr, [$($arg:expr),*], ($task:tt, $pkg:expr, $confirm:expr)) => {
-            assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::$task { pkg: $pkg.to_string(), confirm: $confirm })
+            assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::$task { pkgs: specs($pkg), confirm: $confirm })
+        };
+        ($vendor:expr, [$($arg:expr),*], (Search, query=$query:expr)) => {
+            assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::Search { query: $query.to_string() })
         };
-        ($vendor:expr, [$($arg:expr),*], ($task:tt, pkg=$pkg:expr)) => {
-            assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::$task { pkg: $pkg.to_string() })
+        ($vendor:expr, [$($arg:expr),*], (Info, pkgs=$pkgs:expr)) => {
+            assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::Info { pkgs: specs($pkgs) })
         };
         ($vendor:expr, [$($arg:expr),*], ($task:tt, confirm=$confirm:expr)) => {
             assert_eq!($vendor.parse(&[ $($arg.to_string()),* ], "-").unwrap(), Task::$task { confirm: $confirm })
@@ -576,13 +1225,13 @@ mod tests {
             (Remove, "vim jq", true)
         );
         check_parse!(upt, ["upt", "upgrade", "vim"], (Upgrade, "vim", false));
-        check_parse!(upt, ["upt", "search", "vim"], (Search, pkg = "vim"));
+        check_parse!(upt, ["upt", "search", "vim"], (Search, query = "vim"));
         check_parse!(
             upt,
             ["upt", "search", "vim", "jq"],
-            (Search, pkg = "vim jq")
+            (Search, query = "vim jq")
         );
-        check_parse!(upt, ["upt", "info", "vim"], (Info, pkg = "vim"));
+        check_parse!(upt, ["upt", "info", "vim"], (Info, pkgs = "vim"));
         check_parse!(upt, ["upt", "update"], UpdateIndex);
         check_parse!(upt, ["upt", "upgrade"], (UpgradeAll, confirm = false));
         check_parse!(upt, ["upt", "upgrade", "-y"], (UpgradeAll, confirm = true));
@@ -590,14 +1239,24 @@ mod tests {
         check_parse!(upt, ["upt", "install"]);
         check_parse!(upt, ["upt", "install", "--ye"]);
         check_parse!(upt, ["upt", "update", "--yes"]);
+
+        let apt = init_vendor("apt").unwrap();
+        check_parse!(
+            apt,
+            ["apt-get", "install", "-y", "vim"],
+            (Install, "vim", true)
+        );
+        check_parse!(apt, ["apt-cache", "search", "vim"], (Search, query = "vim"));
+        check_parse!(apt, ["apt-cache", "show", "vim"], (Info, pkgs = "vim"));
+        check_parse!(apt, ["apt-cache", "install", "vim"]);
     }
 
     macro_rules! check_eval {
         ($vendor:expr, ($task:tt, $pkg:expr, $confirm:expr), $cmd:expr) => {
             assert_eq!(
                 $vendor
-                    .eval(&Task::$task {
-                        pkg: $pkg.to_string(),
+                    .argv(&Task::$task {
+                        pkgs: specs($pkg),
                         confirm: $confirm
                     })
                     .unwrap()
@@ -605,21 +1264,30 @@ mod tests {
                 $cmd.to_string()
             )
         };
-        ($vendor:expr, ($task:tt, pkg=$pkg:expr), $cmd:expr) => {
+        ($vendor:expr, (Search, query=$query:expr), $cmd:expr) => {
             assert_eq!(
                 $vendor
-                    .eval(&Task::$task {
-                        pkg: $pkg.to_string()
+                    .argv(&Task::Search {
+                        query: $query.to_string()
                     })
                     .unwrap()
                     .join(" "),
                 $cmd.to_string()
             )
         };
+        ($vendor:expr, (Info, pkgs=$pkgs:expr), $cmd:expr) => {
+            assert_eq!(
+                $vendor
+                    .argv(&Task::Info { pkgs: specs($pkgs) })
+                    .unwrap()
+                    .join(" "),
+                $cmd.to_string()
+            )
+        };
         ($vendor:expr, ($task:tt, confirm=$confirm:expr), $cmd:expr) => {
             assert_eq!(
                 $vendor
-                    .eval(&Task::$task { confirm: $confirm })
+                    .argv(&Task::$task { confirm: $confirm })
                     .unwrap()
                     .join(" "),
                 $cmd.to_string()
@@ -627,12 +1295,12 @@ mod tests {
         };
         ($vendor:expr, $task:tt, $cmd:expr) => {
             assert_eq!(
-                $vendor.eval(&Task::$task).unwrap().join(" "),
+                $vendor.argv(&Task::$task).unwrap().join(" "),
                 $cmd.to_string()
             )
         };
         ($vendor:expr) => {
-            assert!($vendor.eval(&Task::$task).is_none())
+            assert!($vendor.argv(&Task::$task).is_none())
         };
     }
 
@@ -643,8 +1311,8 @@ mod tests {
         check_eval!(upt, (Install, "vim jq", true), "upt install vim jq -y");
         check_eval!(upt, (Remove, "vim jq", false), "upt remove vim jq");
         check_eval!(upt, (Upgrade, "vim", true), "upt upgrade vim -y");
-        check_eval!(upt, (Search, pkg = "vim"), "upt search vim");
-        check_eval!(upt, (Info, pkg = "vim"), "upt info vim");
+        check_eval!(upt, (Search, query = "vim"), "upt search vim");
+        check_eval!(upt, (Info, pkgs = "vim"), "upt info vim");
         check_eval!(upt, UpdateIndex, "upt update");
         check_eval!(upt, (UpgradeAll, confirm = false), "upt upgrade");
         check_eval!(upt, (UpgradeAll, confirm = true), "upt upgrade -y");
@@ -659,8 +1327,8 @@ mod tests {
         );
         check_eval!(pacman, (Remove, "vim jq", false), "pacman -R -s vim jq");
         check_eval!(pacman, (Upgrade, "vim", true), "pacman -S vim --noconfirm");
-        check_eval!(pacman, (Search, pkg = "vim"), "pacman -S -s vim");
-        check_eval!(pacman, (Info, pkg = "vim"), "pacman -S -i vim");
+        check_eval!(pacman, (Search, query = "vim"), "pacman -S -s vim");
+        check_eval!(pacman, (Info, pkgs = "vim"), "pacman -S -i vim");
         check_eval!(pacman, UpdateIndex, "pacman -S -y");
         check_eval!(pacman, (UpgradeAll, confirm = false), "pacman -S -y -u");
         check_eval!(
@@ -671,6 +1339,166 @@ mod tests {
         check_eval!(pacman, ListInstalled, "pacman -Q");
     }
 
+    #[test]
+    fn test_eval_version() {
+        let pkgs = vec![
+            PackageSpec::new("vim"),
+            PackageSpec::with_version("jq", "1.6"),
+        ];
+        let task = Task::Install {
+            pkgs,
+            confirm: false,
+        };
+        let argv = |tool: &str| init_vendor(tool).unwrap().argv(&task).map(|v| v.join(" "));
+        assert_eq!(argv("apt").unwrap(), "apt install vim jq=1.6");
+        assert_eq!(argv("dnf").unwrap(), "dnf install vim jq-1.6");
+        assert_eq!(argv("brew").unwrap(), "brew install vim jq@1.6");
+        assert_eq!(argv("emerge").unwrap(), "emerge vim =jq-1.6");
+        assert_eq!(
+            argv("pacman").unwrap_err(),
+            UptError::NoVersion("pacman".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_vendor() {
+        let mut runner = RecordingRunner::new();
+        runner.missing("pkgman");
+        assert_eq!(
+            select_vendor_with(&runner, "pkgman, cards").unwrap().name(),
+            "cards"
+        );
+        // Unsupported tools do not end the list
+        assert_eq!(
+            select_vendor_with(&runner, "foo,pkgman,cards")
+                .unwrap()
+                .name(),
+            "cards"
+        );
+        runner.missing("cards");
+        assert_eq!(
+            select_vendor_with(&runner, "foo, pkgman, cards").unwrap_err(),
+            UptError::NoAvailableVendor(vec![
+                "foo (not supported)".to_string(),
+                "pkgman".to_string(),
+                "cards".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_persona_vendor() {
+        assert_eq!(persona_vendor("apt").unwrap().name(), "apt");
+        assert_eq!(persona_vendor("apt-get").unwrap().name(), "apt");
+        assert_eq!(persona_vendor("apt-cache").unwrap().name(), "apt");
+        assert_eq!(persona_vendor("xbps-install").unwrap().name(), "xbps");
+        assert_eq!(persona_vendor("urpme").unwrap().name(), "urpm");
+        assert!(persona_vendor("foo").is_err());
+        // Helper commands of a single action are not personas
+        assert!(persona_vendor("rpm").is_err());
+        assert!(persona_vendor("ls").is_err());
+        assert!(persona_vendor("qlist").is_err());
+    }
+
+    #[test]
+    fn test_os_table() {
+        for (os, tools) in os_table() {
+            for tool in tools {
+                assert!(init_vendor(tool).is_ok(), "{os} uses unsupported {tool}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_locate() {
+        let emerge = init_vendor("emerge").unwrap();
+        let mut runner = RecordingRunner::new();
+        assert_eq!(
+            emerge.locate_with(&runner, "qlist"),
+            Ok(PathBuf::from("/usr/bin/qlist"))
+        );
+        runner.missing("qlist");
+        assert_eq!(
+            emerge.locate_with(&runner, "qlist").unwrap_err(),
+            UptError::NoCommand(
+                "qlist".to_string(),
+                Some("app-portage/portage-utils".to_string())
+            )
+        );
+        // Only the binaries of the tool's actions are hinted
+        runner.missing("rpm");
+        let apt = init_vendor("apt").unwrap();
+        assert_eq!(
+            apt.locate_with(&runner, "rpm").unwrap_err(),
+            UptError::NoCommand("rpm".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_providers() {
+        let vendors: Vec<Vendor> = support_tools()
+            .into_iter()
+            .map(|v| init_vendor(v).unwrap())
+            .collect();
+        for (bin_name, _) in PROVIDERS {
+            assert!(
+                vendors.iter().any(|v| v.binaries().contains(&bin_name)),
+                "no action runs {bin_name}"
+            );
+        }
+        // The binaries of other packages say which package to install
+        for vendor in &vendors {
+            for bin_name in vendor.binaries() {
+                assert!(
+                    vendor.personas().contains(&bin_name)
+                        || PROVIDERS.iter().any(|(v, _)| *v == bin_name),
+                    "{} runs {bin_name} without a provider",
+                    vendor.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let dnf = init_vendor("dnf").unwrap();
+        assert_eq!(
+            dnf.exit_code("update_index", 100),
+            ExitCode::UpdatesAvailable
+        );
+        assert_eq!(dnf.exit_code("install", 100), ExitCode::Failure);
+        assert_eq!(dnf.exit_code("install", 0), ExitCode::Success);
+        assert!(dnf.translates_exit_codes("update_index"));
+        // Any failed install is 1 already, an interactive one may run without upt
+        assert!(!dnf.translates_exit_codes("install"));
+        let brew = init_vendor("brew").unwrap();
+        assert!(!brew.translates_exit_codes("install"));
+        assert!(brew.translates_exit_codes("info"));
+        for tool in ["pacman", "yay", "emerge"] {
+            assert!(!init_vendor(tool).unwrap().translates_exit_codes("install"));
+        }
+        assert!(init_vendor("zypper")
+            .unwrap()
+            .translates_exit_codes("install"));
+        let zypper = init_vendor("zypper").unwrap();
+        assert_eq!(zypper.exit_code("install", 104), ExitCode::PackageNotFound);
+        // apt fails with 100 for any error, the message tells it needs root
+        let apt = init_vendor("apt").unwrap();
+        assert!(apt.may_deny("install"));
+        assert!(!apt.may_deny("search"));
+        assert!(apt.denied(b"E: Unable to acquire the dpkg frontend lock, are you root?\n"));
+        assert!(!apt.denied(b"E: Unable to locate package vim\n"));
+        let winget = init_vendor("winget").unwrap();
+        assert_eq!(
+            winget.exit_code("upgrade", -1978335189),
+            ExitCode::NothingToDo
+        );
+        for (tool, action, _, _) in EXIT_CODES {
+            init_vendor(tool).unwrap();
+            assert!(action == "*" || ACTIONS.contains(&action));
+        }
+    }
+
     #[test]
     fn test_vendors() {
         for tool in support_tools() {
@@ -0,0 +1,221 @@
+//! Runs the upt binary against fake tools, which log their argv instead of managing packages
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A directory of fake programs, the only one on the PATH of upt
+struct Fakes {
+    dir: PathBuf,
+}
+
+impl Fakes {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("upt-cli-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        symlink(which::which("sh").unwrap(), dir.join("sh")).unwrap();
+        Fakes { dir }
+    }
+
+    /// A program logging its name and arguments, then running the body
+    fn script(&self, name: &str, body: &str) {
+        let path = self.dir.join(name);
+        let log = self.dir.join("log");
+        let text = format!(
+            "#!/bin/sh\necho \"{name} $*\" >> '{}'\n{body}\n",
+            log.display()
+        );
+        fs::write(&path, text).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.dir.join(name).display().to_string()
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_upt"));
+        command
+            .args(args)
+            .env_clear()
+            .env("PATH", &self.dir)
+            .stdin(Stdio::null());
+        command
+    }
+
+    fn upt(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    fn clear_log(&self) {
+        let _ = fs::remove_file(self.dir.join("log"));
+    }
+
+    /// The commands run so far, in order
+    fn log(&self) -> Vec<String> {
+        fs::read_to_string(self.dir.join("log"))
+            .unwrap_or_default()
+            .lines()
+            .map(|v| v.to_string())
+            .collect()
+    }
+}
+
+impl Drop for Fakes {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn test_ssh() {
+    // The fake ssh runs the remote line with the fake programs
+    let fakes = Fakes::new("ssh");
+    fakes.script("ssh", "shift 3\nexec sh -c \"$1\"");
+    fakes.script("cat", "echo ID=fedora");
+    fakes.script("dnf", "");
+    let output = fakes.upt(&["--host", "root@box", "install", "vim", "-y"]);
+    assert!(output.status.success(), "{output:?}");
+    // The candidate tools are probed over one connection, the detected dnf is run by its path
+    assert_eq!(
+        fakes.log(),
+        [
+            "ssh -T -- root@box cat /etc/os-release 2>/dev/null || uname -s".to_string(),
+            "cat /etc/os-release".to_string(),
+            "ssh -T -- root@box for p in dnf yum; do command -v \"$p\" || echo; done".to_string(),
+            format!("ssh -T -- root@box {} install vim -y", fakes.path("dnf")),
+            "dnf install vim -y".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_container() {
+    // The fake podman runs the command with the fake programs, podman is preferred over docker
+    let fakes = Fakes::new("container");
+    fakes.script("podman", "shift 3\nexec \"$@\"");
+    fakes.script("docker", "shift 3\nexec \"$@\"");
+    fakes.script("cat", "echo ID=debian");
+    fakes.script("apt", "");
+    let output = fakes.upt(&["--container", "dev", "remove", "vim", "-y"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fakes.log(),
+        [
+            "podman exec -i dev sh -c cat /etc/os-release 2>/dev/null || uname -s".to_string(),
+            "cat /etc/os-release".to_string(),
+            "podman exec -i dev sh -c for p in apt; do command -v \"$p\" || echo; done".to_string(),
+            format!("podman exec -i dev {} remove vim -y", fakes.path("apt")),
+            "apt remove vim -y".to_string(),
+        ]
+    );
+
+    // The engine is picked with a prefix
+    fakes.clear_log();
+    let output = fakes.upt(&["--container", "docker:dev", "install", "vim"]);
+    assert!(output.status.success(), "{output:?}");
+    let log = fakes.log();
+    assert!(log.iter().all(|v| !v.starts_with("podman")));
+    assert_eq!(
+        log[log.len() - 2..],
+        [
+            format!("docker exec -i dev {} install vim", fakes.path("apt")),
+            "apt install vim".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_signals() {
+    // The fake apk waits until it is signaled, then tells which signal reached it
+    let fakes = Fakes::new("signals");
+    symlink(which::which("sleep").unwrap(), fakes.dir.join("sleep")).unwrap();
+    let log = fakes.path("log");
+    fakes.script(
+        "apk",
+        &format!(
+            "trap 'kill $!; echo got INT >> {log}; exit 130' INT\n\
+             trap 'kill $!; echo got TERM >> {log}; exit 143' TERM\n\
+             sleep 10 & wait"
+        ),
+    );
+    for (sig, name) in [(libc::SIGINT, "INT"), (libc::SIGTERM, "TERM")] {
+        fakes.clear_log();
+        let mut child = fakes
+            .command(&["install", "vim"])
+            .env("UPT_TOOL", "apk")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let start = Instant::now();
+        while fakes.log().is_empty() && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        // Let the fake set its traps and start waiting
+        thread::sleep(Duration::from_millis(200));
+        // SAFETY: the pid is the one of the running upt
+        unsafe {
+            libc::kill(child.id() as i32, sig);
+        }
+        let status = child.wait().unwrap();
+        assert_eq!(status.code(), Some(128 + sig), "{name}");
+        assert_eq!(
+            fakes.log(),
+            ["apk add vim".to_string(), format!("got {name}")]
+        );
+    }
+}
+
+#[test]
+fn test_search_not_found() {
+    // Like apt, the fake exits 0 whether it finds something or not
+    let fakes = Fakes::new("search");
+    fakes.script(
+        "apt",
+        "[ \"$2\" = vim ] && printf 'vim/stable 2:9.0.1378-2 amd64\\n  Vi IMproved\\n'\nexit 0",
+    );
+    let output = fakes
+        .command(&["search", "vim"])
+        .env("UPT_TOOL", "apt")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    // Without a format the output is printed as the tool wrote it
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "vim/stable 2:9.0.1378-2 amd64\n  Vi IMproved\n"
+    );
+    let output = fakes
+        .command(&["search", "no-such-package"])
+        .env("UPT_TOOL", "apt")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+}
+
+#[test]
+fn test_export() {
+    // Only the explicitly installed packages unless --all is given
+    let fakes = Fakes::new("export");
+    fakes.script(
+        "apt",
+        "echo 'git/stable,now 1:2.39.2-1.1 amd64 [installed]'\n\
+         echo 'fd-find/stable,now 8.6.0-3 amd64 [installed,automatic]'",
+    );
+    fakes.script("apt-mark", "echo git");
+    let export = |args: &[&str]| {
+        let output = fakes.command(args).env("UPT_TOOL", "apt").output().unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(export(&["export", "--format", "txt"]), "git\n");
+    assert_eq!(export(&["export", "--all", "--format", "txt"]), "git\nfd\n");
+    let output = fakes.upt(&["export", "--all", "--explicit"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+}